@@ -0,0 +1,272 @@
+//! A single color value, that can be parsed from and printed as `CSS` color syntax.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::css::{parse_color, NAMED_COLORS};
+use super::models::{decode_color, encode_pixel, family, hsl_to_rgb, rgb_to_hsl};
+use super::{packed, spaces, ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// A single color, stored as normalized, `sRGB` encoded `RGBA`.
+///
+/// Channels are usually within `0.0-1.0`, but colors outside of the `sRGB` gamut (e.g. parsed from `lab()` or `oklch()`)
+/// keep their values outside of it, so they can be converted back without loss. They are only clipped when converted
+/// to integer channels or printed as hex or `rgb()`.
+///
+/// A `Color` can be parsed from any `CSS Color 4` string with [`str::parse`], and printed with the `to_css_*` methods,
+/// or as hex with `Display`. Supported syntaxes are:
+/// - hex colors: `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`
+/// - the 148 named colors, and `transparent`
+/// - `rgb()`/`rgba()` and `hsl()`/`hsla()`, with both the modern and the legacy comma syntax
+/// - `hwb()`, `lab()`, `lch()`, `oklab()` and `oklch()`
+/// - `color()` with the `srgb`, `srgb-linear`, `display-p3`, `rec2020`, `a98-rgb`, `xyz`, `xyz-d50` and `xyz-d65` color spaces
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{from_rgb_to_cmyk, Color};
+///
+/// let orange: Color = "#ff8000".parse().unwrap();
+/// let [r, g, b] = orange.to_rgb8();
+/// let cmyk = from_rgb_to_cmyk(r, g, b);
+///
+/// assert_eq!("rgb(255 128 0)".parse(), Ok(orange));
+/// assert_eq!("hsl(30.12deg, 100%, 50%)".parse::<Color>().unwrap().to_rgb8(), [255, 128, 0]);
+/// assert_eq!("rebeccapurple".parse::<Color>().unwrap().to_hex(), "#663399");
+///
+/// assert_eq!(orange.to_css_oklch(), "oklch(73.19% 0.1858 52.98)");
+/// assert_eq!(Color::from_rgba8(255, 0, 0, 128).to_string(), "#ff000080");
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    /// Creates a new color from normalized, `sRGB` encoded channels.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        return Self { r, g, b, a };
+    }
+
+    /// Creates a new opaque color from 8-bit `sRGB` channels.
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Self {
+        return Self::from_rgba8(r, g, b, 255);
+    }
+
+    /// Creates a new color from 8-bit `sRGB` channels and alpha.
+    pub fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        return Self::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0);
+    }
+
+    /// Returns the color as 8-bit `sRGB` channels, clipping it to the `sRGB` gamut.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        let [r, g, b, _] = self.to_rgba8();
+        return [r, g, b];
+    }
+
+    /// Returns the color as 8-bit `sRGB` channels and alpha, clipping it to the `sRGB` gamut.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        return [self.r, self.g, self.b, self.a].map(u8::from_normalized);
+    }
+
+    /// Creates a color from a single pixel in any [`ColorFormat`].
+    ///
+    /// ## Returns
+    /// A `Result` containing either the color, or a [`ColorFormatConverterError`] if `pixel` isn't exactly one pixel long,
+    /// or can't be decoded (see [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic)).
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{Color, ColorFormat};
+    ///
+    /// let red = Color::from_pixel(&[0u8, 255, 255, 0], ColorFormat::CMYK).unwrap();
+    /// assert_eq!(red.to_hex(), "#ff0000");
+    /// ```
+    pub fn from_pixel<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<Self, ColorFormatConverterError> {
+        let [r, g, b, a] = decode_color(pixel, format)?;
+        return Ok(Self::new(r, g, b, a));
+    }
+
+    /// Converts the color into a single pixel in any [`ColorFormat`].
+    ///
+    /// ## Returns
+    /// A `Result` containing either the pixel's channels, or `UnsupportedFormat` for formats the channel type can't hold,
+    /// like `Lab` with integer channels.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{Color, ColorFormat};
+    ///
+    /// let orange: Color = "#ff8000".parse().unwrap();
+    /// assert_eq!(orange.to_pixel::<u8>(ColorFormat::BGRA), Ok(vec![0, 128, 255, 255]));
+    /// ```
+    pub fn to_pixel<T: ChannelType>(&self, format: ColorFormat) -> Result<Vec<T>, ColorFormatConverterError> {
+        if !T::IS_FLOAT && family(format).is_unbounded() {
+            return Err(ColorFormatConverterError::UnsupportedFormat);
+        }
+        let rgba = [self.r, self.g, self.b, self.a];
+        let mut out = Vec::with_capacity(format.bytes_per_pixel());
+
+        if format.is_packed() {
+            let rgba: Vec<T> = rgba.iter().map(|&v| T::from_normalized(v)).collect();
+            packed::convert_packed(&rgba, ColorFormat::RGBA, format, None, &mut out)?;
+            return Ok(out);
+        }
+        encode_pixel(rgba, format, &mut out);
+        return Ok(out);
+    }
+
+    /// Creates a color from hue in degrees, saturation and lightness in `0.0-1.0`, and alpha.
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let (r, g, b) = hsl_to_rgb(h, s, l);
+        return Self::new(r, g, b, a);
+    }
+
+    /// Returns the color as hue in degrees, saturation and lightness in `0.0-1.0`.
+    /// Colors that are gray, up to rounding errors, have a hue and saturation of `0.0`.
+    pub fn to_hsl(&self) -> [f32; 3] {
+        let (h, s, l) = rgb_to_hsl(self.r, self.g, self.b);
+        if self.r.max(self.g).max(self.b) - self.r.min(self.g).min(self.b) < 1e-4 {
+            return [0.0, 0.0, l];
+        }
+        return [h, s, l];
+    }
+
+    /// Creates a color from `OKLab` lightness, `a` and `b`, and alpha.
+    pub fn from_oklab(l: f32, a: f32, b: f32, alpha: f32) -> Self {
+        let [r, g, b] = spaces::oklab_to_srgb([l, a, b]);
+        return Self::new(r, g, b, alpha);
+    }
+
+    /// Returns the color as `OKLab` lightness, `a` and `b`.
+    pub fn to_oklab(&self) -> [f32; 3] {
+        return spaces::srgb_to_oklab([self.r, self.g, self.b]);
+    }
+
+    /// Creates a color from `OKLCH` lightness, chroma and hue in degrees, and alpha.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::Color;
+    ///
+    /// let color = Color::from_oklch(0.7319, 0.1858, 52.98, 1.0);
+    /// assert_eq!(color.to_hex(), "#ff8000");
+    /// ```
+    pub fn from_oklch(l: f32, c: f32, h: f32, alpha: f32) -> Self {
+        let [l, a, b] = spaces::from_polar([l, c, h]);
+        return Self::from_oklab(l, a, b, alpha);
+    }
+
+    /// Returns the color as `OKLCH` lightness, chroma and hue in degrees.
+    pub fn to_oklch(&self) -> [f32; 3] {
+        return spaces::to_polar(self.to_oklab());
+    }
+
+    /// Returns the color as a hex string, `#rrggbb`, or `#rrggbbaa` if it isn't opaque.
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        if a == 255 {
+            return format!("#{:02x}{:02x}{:02x}", r, g, b);
+        }
+        return format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a);
+    }
+
+    /// Returns the name of the color, if it is opaque and exactly matches one of the `CSS` named colors.
+    ///
+    /// Some colors have two names (e.g. `aqua` and `cyan`, or `gray` and `grey`), in which case the first alphabetically is returned.
+    pub fn to_css_name(&self) -> Option<&'static str> {
+        let [r, g, b, a] = self.to_rgba8();
+        let value = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+        return NAMED_COLORS.iter()
+            .find(|(_, hex)| a == 255 && *hex == value)
+            .map(|(name, _)| *name);
+    }
+
+    /// Returns the color as `rgb()`, e.g. `rgb(255 128 0)` or `rgb(255 128 0 / 0.5)`.
+    pub fn to_css_rgb(&self) -> String {
+        let [r, g, b] = self.to_rgb8();
+        return css_function("rgb", [r.to_string(), g.to_string(), b.to_string()], self.a);
+    }
+
+    /// Returns the color as `hsl()`, e.g. `hsl(30.12 100% 50%)`.
+    pub fn to_css_hsl(&self) -> String {
+        let [h, s, l] = self.to_hsl();
+        return css_function("hsl", [number(h, 2), percent(s, 2), percent(l, 2)], self.a);
+    }
+
+    /// Returns the color as `hwb()`, e.g. `hwb(30.12 0% 0%)`.
+    pub fn to_css_hwb(&self) -> String {
+        let [h, _, _] = self.to_hsl();
+        let white = self.r.min(self.g).min(self.b);
+        let black = 1.0 - self.r.max(self.g).max(self.b);
+        return css_function("hwb", [number(h, 2), percent(white, 2), percent(black, 2)], self.a);
+    }
+
+    /// Returns the color as `lab()`, relative to `D50`, e.g. `lab(67.82 45.49 74.84)`.
+    pub fn to_css_lab(&self) -> String {
+        let [l, a, b] = spaces::srgb_to_lab([self.r, self.g, self.b]);
+        return css_function("lab", [number(l, 2), number(a, 2), number(b, 2)], self.a);
+    }
+
+    /// Returns the color as `lch()`, relative to `D50`, e.g. `lch(67.82 87.58 58.7)`.
+    pub fn to_css_lch(&self) -> String {
+        let [l, c, h] = spaces::to_polar(spaces::srgb_to_lab([self.r, self.g, self.b]));
+        let chroma = number(c, 2);
+        let hue = if chroma == "0" { "0".to_string() } else { number(h, 2) };
+        return css_function("lch", [number(l, 2), chroma, hue], self.a);
+    }
+
+    /// Returns the color as `oklab()`, e.g. `oklab(73.19% 0.1119 0.1484)`.
+    pub fn to_css_oklab(&self) -> String {
+        let [l, a, b] = self.to_oklab();
+        return css_function("oklab", [percent(l, 2), number(a, 4), number(b, 4)], self.a);
+    }
+
+    /// Returns the color as `oklch()`, e.g. `oklch(73.19% 0.1858 52.98)`.
+    pub fn to_css_oklch(&self) -> String {
+        let [l, c, h] = self.to_oklch();
+        let chroma = number(c, 4);
+        let hue = if chroma == "0" { "0".to_string() } else { number(h, 2) };
+        return css_function("oklch", [percent(l, 2), chroma, hue], self.a);
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorFormatConverterError;
+
+    /// Parses any supported `CSS` color string, see [`Color`]. Names and functions are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [r, g, b, a] = parse_color(s)?;
+        return Ok(Self::new(r, g, b, a));
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats the color as hex, see [`Color::to_hex`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.to_hex());
+    }
+}
+
+/// Formats a `CSS` color function with modern syntax, only adding alpha if the color isn't opaque.
+fn css_function(name: &str, values: [String; 3], alpha: f32) -> String {
+    let [a, b, c] = values;
+    if alpha >= 1.0 {
+        return format!("{}({} {} {})", name, a, b, c);
+    }
+    return format!("{}({} {} {} / {})", name, a, b, c, number(alpha.max(0.0), 3));
+}
+
+/// Formats a number with up to `decimals` decimals, without trailing zeros.
+fn number(value: f32, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.') } else { &text };
+    return if text == "-0" { "0".to_string() } else { text.to_string() };
+}
+
+/// Formats a `0.0-1.0` value as a percentage, with up to `decimals` decimals.
+fn percent(value: f32, decimals: usize) -> String {
+    return format!("{}%", number(value * 100.0, decimals));
+}
@@ -0,0 +1,305 @@
+//! Alpha compositing: premultiplied alpha, the Porter-Duff operators and separable blend modes.
+//!
+//! Operations work on every color channel separately, so they take any `RGB`, `LinearRGB` or `Gray` format, with or without alpha
+//! (a missing alpha channel counts as opaque). Blending is done on the values as they are stored, so on gamma-encoded values for `RGB`,
+//! like image editors and browsers do, and on linear light for `LinearRGB`.
+//! Unless stated otherwise, buffers hold straight (not premultiplied) alpha.
+
+use super::models::{family, Family};
+use super::{channel_order, check_values, Channel, ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// The Porter-Duff compositing operators, placing a source over a destination (the backdrop).
+///
+/// - `Over`: The source over the destination, the usual way of layering.
+/// - `In`: The source, only where the destination is.
+/// - `Out`: The source, only where the destination isn't.
+/// - `Atop`: The source over the destination, only where the destination is.
+/// - `Xor`: The source and the destination, only where they don't overlap.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CompositeOperator {
+    Over,
+    In,
+    Out,
+    Atop,
+    Xor,
+}
+
+impl CompositeOperator {
+    /// The fractions of the source and the destination in the result, given their alphas.
+    fn fractions(&self, source_alpha: f32, destination_alpha: f32) -> (f32, f32) {
+        return match self {
+            CompositeOperator::Over => (1.0, 1.0 - source_alpha),
+            CompositeOperator::In => (destination_alpha, 0.0),
+            CompositeOperator::Out => (1.0 - destination_alpha, 0.0),
+            CompositeOperator::Atop => (destination_alpha, 1.0 - source_alpha),
+            CompositeOperator::Xor => (1.0 - destination_alpha, 1.0 - source_alpha),
+        };
+    }
+}
+
+/// The separable blend modes of the `W3C Compositing and Blending` specification,
+/// which mix every channel of a source with the same channel of the destination (the backdrop).
+///
+/// - `Normal`: The source, as is.
+/// - `Multiply`: The product of both, which is always darker, like stacking transparencies.
+/// - `Screen`: The inverse of multiplying the inverses, which is always lighter, like projecting both.
+/// - `Overlay`: `Multiply` for dark destination values and `Screen` for light ones, increasing contrast.
+/// - `Darken`: The darker of both.
+/// - `Lighten`: The lighter of both.
+/// - `ColorDodge`: Brightens the destination to reflect the source.
+/// - `ColorBurn`: Darkens the destination to reflect the source.
+/// - `HardLight`: `Multiply` for dark source values and `Screen` for light ones, like a harsh spotlight.
+/// - `SoftLight`: A softer `HardLight`, like a diffused spotlight.
+/// - `Difference`: The absolute difference of both.
+/// - `Exclusion`: Like `Difference`, with lower contrast.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+}
+
+impl BlendMode {
+    /// Blends a single normalized destination (backdrop) value `b` with a source value `s`.
+    fn blend(&self, b: f32, s: f32) -> f32 {
+        return match self {
+            BlendMode::Normal => s,
+            BlendMode::Multiply => b * s,
+            BlendMode::Screen => b + s - b * s,
+            BlendMode::Overlay => BlendMode::HardLight.blend(s, b),
+            BlendMode::Darken => b.min(s),
+            BlendMode::Lighten => b.max(s),
+            BlendMode::ColorDodge => {
+                if b == 0.0 { 0.0 }
+                else if s >= 1.0 { 1.0 }
+                else { (b / (1.0 - s)).min(1.0) }
+            },
+            BlendMode::ColorBurn => {
+                if b >= 1.0 { 1.0 }
+                else if s <= 0.0 { 0.0 }
+                else { 1.0 - ((1.0 - b) / s).min(1.0) }
+            },
+            BlendMode::HardLight => {
+                if s <= 0.5 { BlendMode::Multiply.blend(b, 2.0 * s) }
+                else { BlendMode::Screen.blend(b, 2.0 * s - 1.0) }
+            },
+            BlendMode::SoftLight => {
+                if s <= 0.5 {
+                    b - (1.0 - 2.0 * s) * b * (1.0 - b)
+                } else {
+                    let d = if b <= 0.25 { ((16.0 * b - 12.0) * b + 4.0) * b } else { b.sqrt() };
+                    b + (2.0 * s - 1.0) * (d - b)
+                }
+            },
+            BlendMode::Difference => (b - s).abs(),
+            BlendMode::Exclusion => b + s - 2.0 * b * s,
+        };
+    }
+}
+
+/// Premultiply the color channels of a buffer by its alpha channel.
+///
+/// Premultiplied alpha is what most compositing, filtering and resampling math expects, since it keeps
+/// the colors of transparent pixels from bleeding into their neighbors.
+///
+/// ## Parameters
+/// - `data`: input slice of pixels with straight alpha, length must be a multiple of `format.channel_count()`
+/// - `format`: an `RGB`, `LinearRGB` or `Gray` format with alpha, e.g. `RGBA`
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format with premultiplied alpha, or a [`ColorFormatConverterError`]:
+/// `UnsupportedFormat` for formats without alpha or of other families, `InvalidInputLength` for an invalid length,
+/// or `OutOfRange` (or `NotANumber`) for invalid float values.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{premultiply, unpremultiply, ColorFormat};
+///
+/// let rgba: Vec<u8> = vec![255, 128, 0, 128,  10, 20, 30, 0];
+/// let premultiplied = premultiply(&rgba, ColorFormat::RGBA).unwrap();
+/// assert_eq!(premultiplied, vec![128, 64, 0, 128,  0, 0, 0, 0]);
+///
+/// // The colors of fully transparent pixels are lost
+/// assert_eq!(unpremultiply(&premultiplied, ColorFormat::RGBA).unwrap(), vec![255, 128, 0, 128,  0, 0, 0, 0]);
+/// ```
+pub fn premultiply<T: ChannelType>(data: &[T], format: ColorFormat) -> Result<Vec<T>, ColorFormatConverterError> {
+    return scale_by_alpha(data, format, |value, alpha| value * alpha);
+}
+
+/// Divide the color channels of a buffer with premultiplied alpha by its alpha channel, making it straight alpha again.
+///
+/// Pixels with an alpha of `0` become black, since their color can't be recovered.
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format with straight alpha, or a [`ColorFormatConverterError`] like [`premultiply`].
+pub fn unpremultiply<T: ChannelType>(data: &[T], format: ColorFormat) -> Result<Vec<T>, ColorFormatConverterError> {
+    return scale_by_alpha(data, format, unpremultiply_value);
+}
+
+/// Composite a source buffer onto a destination buffer with a Porter-Duff operator.
+///
+/// ## Parameters
+/// - `source`: the pixels placed on top
+/// - `destination`: the pixels of the backdrop, of the same length as `source`
+/// - `format`: color model of both buffers, an `RGB`, `LinearRGB` or `Gray` format with or without alpha
+/// - `operator`: the compositing operator
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format, or a [`ColorFormatConverterError`]: `InvalidInputLength` if the buffers
+/// have different or invalid lengths, `UnsupportedFormat` for formats of other families, or `OutOfRange` (or `NotANumber`) for invalid float values.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{composite, ColorFormat, CompositeOperator};
+///
+/// let red: Vec<u8> = vec![255, 0, 0, 128]; // half transparent
+/// let blue: Vec<u8> = vec![0, 0, 255, 255];
+///
+/// assert_eq!(composite(&red, &blue, ColorFormat::RGBA, CompositeOperator::Over).unwrap(), vec![128, 0, 127, 255]);
+/// assert_eq!(composite(&red, &blue, ColorFormat::RGBA, CompositeOperator::In).unwrap(), vec![255, 0, 0, 128]);
+/// assert_eq!(composite(&red, &blue, ColorFormat::RGBA, CompositeOperator::Out).unwrap(), vec![0, 0, 0, 0]);
+/// ```
+pub fn composite<T: ChannelType>(
+    source: &[T],
+    destination: &[T],
+    format: ColorFormat,
+    operator: CompositeOperator
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    return combine(source, destination, format, |s, b, source_alpha, destination_alpha| {
+        let (fa, fb) = operator.fractions(source_alpha, destination_alpha);
+        let alpha = fa * source_alpha + fb * destination_alpha;
+        let color: Vec<f32> = s.iter().zip(b)
+            .map(|(s, b)| unpremultiply_value(fa * s * source_alpha + fb * b * destination_alpha, alpha))
+            .collect();
+        (color, alpha)
+    });
+}
+
+/// Blend a source buffer onto a destination buffer with a blend mode, and composite the result over the destination.
+///
+/// Where the destination is transparent, the source shows through unblended, as specified by `W3C Compositing and Blending`.
+///
+/// ## Parameters
+/// - `source`: the pixels of the blended layer
+/// - `destination`: the pixels of the backdrop, of the same length as `source`
+/// - `format`: color model of both buffers, an `RGB`, `LinearRGB` or `Gray` format with or without alpha
+/// - `mode`: the blend mode
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format, or a [`ColorFormatConverterError`] like [`composite`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{blend, BlendMode, ColorFormat};
+///
+/// let source: Vec<u8> = vec![255, 128, 0];
+/// let destination: Vec<u8> = vec![128, 128, 128];
+///
+/// assert_eq!(blend(&source, &destination, ColorFormat::RGB, BlendMode::Multiply).unwrap(), vec![128, 64, 0]);
+/// assert_eq!(blend(&source, &destination, ColorFormat::RGB, BlendMode::Screen).unwrap(), vec![255, 192, 128]);
+/// assert_eq!(blend(&source, &destination, ColorFormat::RGB, BlendMode::Difference).unwrap(), vec![127, 0, 128]);
+/// ```
+pub fn blend<T: ChannelType>(
+    source: &[T],
+    destination: &[T],
+    format: ColorFormat,
+    mode: BlendMode
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    return combine(source, destination, format, |s, b, source_alpha, destination_alpha| {
+        let alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+        let color: Vec<f32> = s.iter().zip(b).map(|(&s, &b)| {
+            let blended = (1.0 - destination_alpha) * s + destination_alpha * mode.blend(b, s);
+            unpremultiply_value(source_alpha * blended + (1.0 - source_alpha) * destination_alpha * b, alpha)
+        }).collect();
+        (color, alpha)
+    });
+}
+
+fn unpremultiply_value(value: f32, alpha: f32) -> f32 {
+    if alpha <= 0.0 {
+        return 0.0;
+    }
+    return (value / alpha).min(1.0);
+}
+
+/// Checks that the format can be composited and the buffer is valid, and returns the index of the alpha channel, if any.
+fn alpha_index<T: ChannelType>(data: &[T], format: ColorFormat) -> Result<Option<usize>, ColorFormatConverterError> {
+    if format.is_packed() || !matches!(family(format), Family::Rgb | Family::LinearRgb | Family::Gray) {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    let channels = format.channel_count();
+    if data.is_empty() || !data.len().is_multiple_of(channels) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    check_values(data, format)?;
+    return Ok(channel_order(format).iter().position(|&ch| ch == Channel::A));
+}
+
+fn scale_by_alpha<T: ChannelType>(
+    data: &[T],
+    format: ColorFormat,
+    f: impl Fn(f32, f32) -> f32
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    let Some(alpha) = alpha_index(data, format)? else {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    };
+    let mut out = Vec::with_capacity(data.len());
+    for pixel in data.chunks_exact(format.channel_count()) {
+        let a = pixel[alpha].to_normalized();
+        out.extend(pixel.iter().enumerate().map(|(i, &value)| {
+            if i == alpha { value } else { T::from_normalized(f(value.to_normalized(), a)) }
+        }));
+    }
+    return Ok(out);
+}
+
+/// Combines every pixel of `source` and `destination` with `f`, which takes their normalized color channels and alphas,
+/// and returns the resulting color channels and alpha.
+fn combine<T: ChannelType>(
+    source: &[T],
+    destination: &[T],
+    format: ColorFormat,
+    f: impl Fn(&[f32], &[f32], f32, f32) -> (Vec<f32>, f32)
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    let alpha = alpha_index(source, format)?;
+    alpha_index(destination, format)?;
+    if source.len() != destination.len() {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+
+    let channels = format.channel_count();
+    let split = |pixel: &[T]| {
+        let color: Vec<f32> = pixel.iter().enumerate()
+            .filter(|&(i, _)| Some(i) != alpha)
+            .map(|(_, v)| v.to_normalized())
+            .collect();
+        (color, alpha.map_or(1.0, |i| pixel[i].to_normalized()))
+    };
+
+    let mut out = Vec::with_capacity(source.len());
+    for (s, b) in source.chunks_exact(channels).zip(destination.chunks_exact(channels)) {
+        let (source_color, source_alpha) = split(s);
+        let (destination_color, destination_alpha) = split(b);
+        let (color, result_alpha) = f(&source_color, &destination_color, source_alpha, destination_alpha);
+
+        let mut color = color.into_iter();
+        for i in 0..channels {
+            let value = if Some(i) == alpha { result_alpha } else { color.next().unwrap_or(0.0) };
+            out.push(T::from_normalized(value));
+        }
+    }
+    return Ok(out);
+}
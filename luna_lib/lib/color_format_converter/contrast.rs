@@ -0,0 +1,189 @@
+//! Contrast and accessibility checks for pairs of colors, following `WCAG 2.x` and `APCA`.
+//!
+//! Colors are single pixels in any [`ColorFormat`]. Alpha is ignored, so semi-transparent colors should be
+//! composited onto their background first. Colors outside of the `sRGB` gamut are clipped, as a display would.
+
+use super::models::{decode_color, LUMINANCE};
+use super::{ChannelType, ColorFormat, ColorFormatConverterError, TransferFunction};
+
+/// `WCAG 2.x` conformance levels for text contrast.
+///
+/// - `AA`: The minimum level, requiring a contrast ratio of `4.5:1` for normal text, and `3:1` for large text.
+/// - `AAA`: The enhanced level, requiring a contrast ratio of `7:1` for normal text, and `4.5:1` for large text.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum WcagLevel {
+    AA,
+    AAA,
+}
+
+/// Text sizes as distinguished by `WCAG 2.x`.
+///
+/// - `Normal`: Anything smaller than large text.
+/// - `Large`: At least `18pt` (`24px`), or at least `14pt` (`18.66px`) and bold.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TextSize {
+    Normal,
+    Large,
+}
+
+impl WcagLevel {
+    /// The minimum contrast ratio this level requires for the given text size.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{TextSize, WcagLevel};
+    ///
+    /// assert_eq!(WcagLevel::AA.min_ratio(TextSize::Normal), 4.5);
+    /// assert_eq!(WcagLevel::AAA.min_ratio(TextSize::Large), 4.5);
+    /// ```
+    pub fn min_ratio(&self, size: TextSize) -> f32 {
+        return match (self, size) {
+            (WcagLevel::AA, TextSize::Normal) => 4.5,
+            (WcagLevel::AA, TextSize::Large) => 3.0,
+            (WcagLevel::AAA, TextSize::Normal) => 7.0,
+            (WcagLevel::AAA, TextSize::Large) => 4.5,
+        };
+    }
+}
+
+/// Calculate the relative luminance of a color, as defined by `WCAG 2.x`.
+///
+/// ## Parameters
+/// - `pixel`: a single pixel of `format`
+/// - `format`: color model of `pixel`
+///
+/// ## Returns
+/// A `Result` containing either the luminance in `0.0-1.0`, where black is `0.0` and white is `1.0`,
+/// or a [`ColorFormatConverterError`] if the pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{relative_luminance, ColorFormat};
+///
+/// assert_eq!(relative_luminance(&[255u8, 255, 255], ColorFormat::RGB), Ok(1.0));
+/// let green = relative_luminance(&[255u8, 0, 255, 0], ColorFormat::CMYK).unwrap();
+/// assert!((green - 0.7152).abs() < 0.0001);
+/// ```
+pub fn relative_luminance<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let rgb = decode_rgb(pixel, format)?;
+    return Ok(rgb.iter()
+        .zip(LUMINANCE.iter())
+        .map(|(&v, w)| TransferFunction::SRGB.decode(v) * w)
+        .sum());
+}
+
+/// Calculate the `WCAG 2.x` contrast ratio between two colors.
+///
+/// The ratio is the same regardless of which color is the text and which the background.
+///
+/// ## Parameters
+/// - `a`: the first color, a single pixel of `a_format`
+/// - `a_format`: color model of `a`
+/// - `b`: the second color, a single pixel of `b_format`
+/// - `b_format`: color model of `b`
+///
+/// ## Returns
+/// A `Result` containing either the ratio in `1.0-21.0`, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{contrast_ratio, ColorFormat};
+///
+/// let black = [0u8, 0, 0];
+/// let white = [255u8];
+/// assert!((contrast_ratio(&black, ColorFormat::RGB, &white, ColorFormat::Gray).unwrap() - 21.0).abs() < 0.001);
+///
+/// let gray = [119u8, 119, 119];
+/// let ratio = contrast_ratio(&gray, ColorFormat::RGB, &white, ColorFormat::Gray).unwrap();
+/// assert!((ratio - 4.48).abs() < 0.01);
+/// ```
+pub fn contrast_ratio<A: ChannelType, B: ChannelType>(a: &[A], a_format: ColorFormat, b: &[B], b_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let l1 = relative_luminance(a, a_format)?;
+    let l2 = relative_luminance(b, b_format)?;
+    return Ok((l1.max(l2) + 0.05) / (l1.min(l2) + 0.05));
+}
+
+/// Check whether a text and background color have enough contrast for a `WCAG 2.x` conformance level.
+///
+/// ## Parameters
+/// - `text`: the text color, a single pixel of `text_format`
+/// - `text_format`: color model of `text`
+/// - `background`: the background color, a single pixel of `background_format`
+/// - `background_format`: color model of `background`
+/// - `level`: the conformance level to check
+/// - `size`: the size of the text
+///
+/// ## Returns
+/// A `Result` containing either whether the pair passes, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{meets_wcag, ColorFormat, TextSize, WcagLevel};
+///
+/// // #777 on white just fails AA for normal text, but passes for large text
+/// let text = [119u8, 119, 119];
+/// let background = [255u8, 255, 255, 255];
+/// assert_eq!(meets_wcag(&text, ColorFormat::RGB, &background, ColorFormat::RGBA, WcagLevel::AA, TextSize::Normal), Ok(false));
+/// assert_eq!(meets_wcag(&text, ColorFormat::RGB, &background, ColorFormat::RGBA, WcagLevel::AA, TextSize::Large), Ok(true));
+/// ```
+pub fn meets_wcag<A: ChannelType, B: ChannelType>(text: &[A], text_format: ColorFormat, background: &[B], background_format: ColorFormat, level: WcagLevel, size: TextSize) -> Result<bool, ColorFormatConverterError> {
+    let ratio = contrast_ratio(text, text_format, background, background_format)?;
+    return Ok(ratio >= level.min_ratio(size));
+}
+
+/// Calculate the `APCA` lightness contrast (`Lc`) of a text color on a background color (`APCA-W3 0.0.98G`).
+///
+/// Unlike the `WCAG 2.x` ratio, `APCA` is perceptually based and depends on polarity: dark text on a light
+/// background gives a positive value, light text on a dark background a negative one.
+/// As a guideline, `|Lc| >= 75` suits body text, `60` content text, `45` large headlines and `30` non-text elements.
+///
+/// ## Parameters
+/// - `text`: the text color, a single pixel of `text_format`
+/// - `text_format`: color model of `text`
+/// - `background`: the background color, a single pixel of `background_format`
+/// - `background_format`: color model of `background`
+///
+/// ## Returns
+/// A `Result` containing either the contrast in about `-108.0-106.0`, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{apca_contrast, ColorFormat};
+///
+/// let gray = [136u8, 136, 136];
+/// let white = [255u8, 255, 255];
+/// assert!((apca_contrast(&gray, ColorFormat::RGB, &white, ColorFormat::RGB).unwrap() - 63.06).abs() < 0.01);
+/// assert!((apca_contrast(&white, ColorFormat::RGB, &gray, ColorFormat::RGB).unwrap() + 68.54).abs() < 0.01);
+/// ```
+pub fn apca_contrast<A: ChannelType, B: ChannelType>(text: &[A], text_format: ColorFormat, background: &[B], background_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let y_text = apca_luminance(decode_rgb(text, text_format)?);
+    let y_background = apca_luminance(decode_rgb(background, background_format)?);
+
+    if (y_background - y_text).abs() < 0.0005 {
+        return Ok(0.0);
+    }
+
+    let contrast = if y_background > y_text {
+        let sapc = (y_background.powf(0.56) - y_text.powf(0.57)) * 1.14;
+        if sapc < 0.1 { 0.0 } else { sapc - 0.027 }
+    } else {
+        let sapc = (y_background.powf(0.65) - y_text.powf(0.62)) * 1.14;
+        if sapc > -0.1 { 0.0 } else { sapc + 0.027 }
+    };
+    return Ok(contrast * 100.0);
+}
+
+/// Decodes a single pixel into `sRGB` encoded `RGB`, clipped to `0.0-1.0`.
+fn decode_rgb<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<[f32; 3], ColorFormatConverterError> {
+    let rgba = decode_color(pixel, format)?;
+    return Ok([rgba[0], rgba[1], rgba[2]].map(|v| v.clamp(0.0, 1.0)));
+}
+
+/// The `APCA` screen luminance, using a simple `2.4` exponent and a soft clamp for near-black colors.
+fn apca_luminance(rgb: [f32; 3]) -> f32 {
+    let y = 0.2126729 * rgb[0].powf(2.4) + 0.7151522 * rgb[1].powf(2.4) + 0.0721750 * rgb[2].powf(2.4);
+    if y < 0.022 {
+        return y + (0.022 - y).powf(1.414);
+    }
+    return y;
+}
@@ -0,0 +1,259 @@
+//! Parsing of `CSS Color 4` syntax into normalized, `sRGB` encoded `RGBA`.
+
+use super::models::hsl_to_rgb;
+use super::gamut::{from_rgb_space, RgbSpace};
+use super::spaces::{self, WhitePoint};
+use super::ColorFormatConverterError;
+
+/// The 148 `CSS` named colors, sorted by name, as `0xRRGGBB`.
+pub(super) const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF), ("antiquewhite", 0xFAEBD7), ("aqua", 0x00FFFF), ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF), ("beige", 0xF5F5DC), ("bisque", 0xFFE4C4), ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD), ("blue", 0x0000FF), ("blueviolet", 0x8A2BE2), ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887), ("cadetblue", 0x5F9EA0), ("chartreuse", 0x7FFF00), ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50), ("cornflowerblue", 0x6495ED), ("cornsilk", 0xFFF8DC), ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF), ("darkblue", 0x00008B), ("darkcyan", 0x008B8B), ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9), ("darkgreen", 0x006400), ("darkgrey", 0xA9A9A9), ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B), ("darkolivegreen", 0x556B2F), ("darkorange", 0xFF8C00), ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000), ("darksalmon", 0xE9967A), ("darkseagreen", 0x8FBC8F), ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F), ("darkslategrey", 0x2F4F4F), ("darkturquoise", 0x00CED1), ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493), ("deepskyblue", 0x00BFFF), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF), ("firebrick", 0xB22222), ("floralwhite", 0xFFFAF0), ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF), ("gainsboro", 0xDCDCDC), ("ghostwhite", 0xF8F8FF), ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080), ("honeydew", 0xF0FFF0), ("hotpink", 0xFF69B4), ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082), ("ivory", 0xFFFFF0), ("khaki", 0xF0E68C), ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5), ("lawngreen", 0x7CFC00), ("lemonchiffon", 0xFFFACD), ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080), ("lightcyan", 0xE0FFFF), ("lightgoldenrodyellow", 0xFAFAD2), ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90), ("lightgrey", 0xD3D3D3), ("lightpink", 0xFFB6C1), ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA), ("lightskyblue", 0x87CEFA), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE), ("lightyellow", 0xFFFFE0), ("lime", 0x00FF00), ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6), ("magenta", 0xFF00FF), ("maroon", 0x800000), ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD), ("mediumorchid", 0xBA55D3), ("mediumpurple", 0x9370DB), ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE), ("mediumspringgreen", 0x00FA9A), ("mediumturquoise", 0x48D1CC), ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xF5FFFA), ("mistyrose", 0xFFE4E1), ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD), ("navy", 0x000080), ("oldlace", 0xFDF5E6), ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23), ("orange", 0xFFA500), ("orangered", 0xFF4500), ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA), ("palegreen", 0x98FB98), ("paleturquoise", 0xAFEEEE), ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5), ("peachpuff", 0xFFDAB9), ("peru", 0xCD853F), ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD), ("powderblue", 0xB0E0E6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000), ("rosybrown", 0xBC8F8F), ("royalblue", 0x4169E1), ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072), ("sandybrown", 0xF4A460), ("seagreen", 0x2E8B57), ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D), ("silver", 0xC0C0C0), ("skyblue", 0x87CEEB), ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xFFFAFA), ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4), ("tan", 0xD2B48C), ("teal", 0x008080), ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347), ("turquoise", 0x40E0D0), ("violet", 0xEE82EE), ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF), ("whitesmoke", 0xF5F5F5), ("yellow", 0xFFFF00), ("yellowgreen", 0x9ACD32),
+];
+
+/// A single component of a color function.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Token {
+    Number(f32),
+    Percent(f32),
+    /// An angle, in degrees.
+    Angle(f32),
+    /// The `none` keyword, a missing component that resolves to zero.
+    None,
+}
+
+impl Token {
+    fn parse(s: &str) -> Result<Token, ColorFormatConverterError> {
+        if s == "none" {
+            return Ok(Token::None);
+        }
+        if let Some(value) = s.strip_suffix('%') {
+            return Ok(Token::Percent(parse_number(value)?));
+        }
+        let units: [(&str, f32); 4] = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f32::consts::PI), ("turn", 360.0)];
+        for (unit, factor) in units {
+            if let Some(value) = s.strip_suffix(unit) {
+                return Ok(Token::Angle(parse_number(value)? * factor));
+            }
+        }
+        return Ok(Token::Number(parse_number(s)?));
+    }
+
+    /// Resolves a number or percentage, where `100%` equals `reference`.
+    fn value(self, reference: f32) -> Result<f32, ColorFormatConverterError> {
+        return match self {
+            Token::Number(v) => Ok(v),
+            Token::Percent(p) => Ok(p / 100.0 * reference),
+            Token::None => Ok(0.0),
+            Token::Angle(_) => Err(ColorFormatConverterError::InvalidColorString),
+        };
+    }
+
+    /// Resolves a hue, in degrees.
+    fn hue(self) -> Result<f32, ColorFormatConverterError> {
+        return match self {
+            Token::Number(v) | Token::Angle(v) => Ok(v),
+            Token::None => Ok(0.0),
+            Token::Percent(_) => Err(ColorFormatConverterError::InvalidColorString),
+        };
+    }
+}
+
+fn parse_number(s: &str) -> Result<f32, ColorFormatConverterError> {
+    // `f32::from_str` also accepts "inf" and "nan", which aren't CSS numbers
+    if !s.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '+' | '-' | 'e')) {
+        return Err(ColorFormatConverterError::InvalidColorString);
+    }
+    return s.parse::<f32>().ok()
+        .filter(|v| v.is_finite())
+        .ok_or(ColorFormatConverterError::InvalidColorString);
+}
+
+/// Parses any supported `CSS` color string into normalized, `sRGB` encoded `RGBA`.
+pub(super) fn parse_color(input: &str) -> Result<[f32; 4], ColorFormatConverterError> {
+    let s = input.trim().to_ascii_lowercase();
+
+    if let Some(hex) = s.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if s == "transparent" {
+        return Ok([0.0, 0.0, 0.0, 0.0]);
+    }
+    if let Ok(index) = NAMED_COLORS.binary_search_by(|(name, _)| name.cmp(&s.as_str())) {
+        return Ok(unpack_hex(NAMED_COLORS[index].1, 6));
+    }
+
+    let (name, args) = s.strip_suffix(')')
+        .and_then(|inner| inner.split_once('('))
+        .ok_or(ColorFormatConverterError::InvalidColorString)?;
+    let name = name.trim_end();
+
+    if name == "color" {
+        let (space, args) = args.trim_start().split_once(char::is_whitespace).ok_or(ColorFormatConverterError::InvalidColorString)?;
+        let (values, alpha) = parse_args(args, false)?;
+        let [r, g, b] = values.map(|t| t.value(1.0));
+        return Ok(with_alpha(predefined(space, [r?, g?, b?])?, alpha));
+    }
+
+    let legacy = matches!(name, "rgb" | "rgba" | "hsl" | "hsla");
+    let (values, alpha) = parse_args(args, legacy)?;
+    let [v0, v1, v2] = values;
+
+    let rgb = match name {
+        "rgb" | "rgba" => [v0, v1, v2].map(|t| t.value(255.0).map(|v| (v / 255.0).clamp(0.0, 1.0))),
+        "hsl" | "hsla" => {
+            let s = v1.value(100.0)?.max(0.0) / 100.0;
+            let l = v2.value(100.0)? / 100.0;
+            let (r, g, b) = hsl_to_rgb(v0.hue()?, s, l);
+            [Ok(r), Ok(g), Ok(b)]
+        },
+        "hwb" => {
+            let w = v1.value(100.0)?.max(0.0) / 100.0;
+            let b = v2.value(100.0)?.max(0.0) / 100.0;
+            if w + b >= 1.0 {
+                let gray = w / (w + b);
+                [Ok(gray), Ok(gray), Ok(gray)]
+            } else {
+                let (r, g, bl) = hsl_to_rgb(v0.hue()?, 1.0, 0.5);
+                [r, g, bl].map(|c| Ok(c * (1.0 - w - b) + w))
+            }
+        },
+        "lab" => {
+            let lab = [v0.value(100.0)?.clamp(0.0, 100.0), v1.value(125.0)?, v2.value(125.0)?];
+            spaces::lab_to_srgb(lab).map(Ok)
+        },
+        "lch" => {
+            let lch = [v0.value(100.0)?.clamp(0.0, 100.0), v1.value(150.0)?.max(0.0), v2.hue()?];
+            spaces::lab_to_srgb(spaces::from_polar(lch)).map(Ok)
+        },
+        "oklab" => {
+            let lab = [v0.value(1.0)?.clamp(0.0, 1.0), v1.value(0.4)?, v2.value(0.4)?];
+            spaces::oklab_to_srgb(lab).map(Ok)
+        },
+        "oklch" => {
+            let lch = [v0.value(1.0)?.clamp(0.0, 1.0), v1.value(0.4)?.max(0.0), v2.hue()?];
+            spaces::oklab_to_srgb(spaces::from_polar(lch)).map(Ok)
+        },
+        _ => return Err(ColorFormatConverterError::InvalidColorString),
+    };
+    let [r, g, b] = rgb;
+    return Ok(with_alpha([r?, g?, b?], alpha));
+}
+
+/// Converts the values of a predefined color space of the `color()` function into `sRGB`.
+fn predefined(space: &str, values: [f32; 3]) -> Result<[f32; 3], ColorFormatConverterError> {
+    return match space {
+        "srgb" => Ok(values),
+        "srgb-linear" => Ok(spaces::linear_to_srgb(values)),
+        "xyz" | "xyz-d65" => Ok(spaces::linear_to_srgb(spaces::xyz_to_linear_srgb(values))),
+        "xyz-d50" => Ok(spaces::linear_to_srgb(spaces::xyz_to_linear_srgb(spaces::adapt(values, WhitePoint::D50, WhitePoint::D65)))),
+        "display-p3" | "rec2020" | "a98-rgb" => {
+            let space = match space {
+                "display-p3" => RgbSpace::DisplayP3,
+                "rec2020" => RgbSpace::Rec2020,
+                _ => RgbSpace::AdobeRGB,
+            };
+            let color = from_rgb_space(values, space, 1.0);
+            Ok([color.r, color.g, color.b])
+        },
+        _ => Err(ColorFormatConverterError::InvalidColorString),
+    };
+}
+
+/// Splits the arguments of a color function into its three components and alpha.
+///
+/// The modern syntax separates components with spaces and alpha with a `/`,
+/// the legacy syntax (only allowed when `legacy` is set) separates everything with commas.
+fn parse_args(args: &str, legacy: bool) -> Result<([Token; 3], Token), ColorFormatConverterError> {
+    let (components, alpha): (Vec<&str>, Option<&str>) = if args.contains(',') {
+        if !legacy || args.contains('/') {
+            return Err(ColorFormatConverterError::InvalidColorString);
+        }
+        let mut parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        let alpha = if parts.len() == 4 { parts.pop() } else { None };
+        (parts, alpha)
+    } else {
+        let (components, alpha) = match args.split_once('/') {
+            Some((components, alpha)) => (components, Some(alpha.trim())),
+            None => (args, None),
+        };
+        (components.split_whitespace().collect(), alpha)
+    };
+
+    if components.len() != 3 {
+        return Err(ColorFormatConverterError::InvalidColorString);
+    }
+    let alpha = match alpha.map(Token::parse).transpose()? {
+        Some(Token::Angle(_)) => return Err(ColorFormatConverterError::InvalidColorString),
+        Some(a) => a,
+        None => Token::Number(1.0),
+    };
+    return Ok(([Token::parse(components[0])?, Token::parse(components[1])?, Token::parse(components[2])?], alpha));
+}
+
+fn with_alpha(rgb: [f32; 3], alpha: Token) -> [f32; 4] {
+    let a = match alpha {
+        Token::Number(v) => v,
+        Token::Percent(p) => p / 100.0,
+        Token::None => 0.0,
+        Token::Angle(_) => unreachable!("Angles are rejected when parsing alpha."),
+    };
+    return [rgb[0], rgb[1], rgb[2], a.clamp(0.0, 1.0)];
+}
+
+fn parse_hex(hex: &str) -> Result<[f32; 4], ColorFormatConverterError> {
+    if !matches!(hex.len(), 3 | 4 | 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ColorFormatConverterError::InvalidColorString);
+    }
+    let value = u32::from_str_radix(hex, 16).map_err(|_| ColorFormatConverterError::InvalidColorString)?;
+    return Ok(unpack_hex(value, hex.len()));
+}
+
+/// Unpacks a hex value of the given digit count, where the short forms repeat every digit.
+fn unpack_hex(value: u32, digits: usize) -> [f32; 4] {
+    let (bits, scale) = if digits <= 4 { (4, 15.0) } else { (8, 255.0) };
+    let count = if digits == 3 || digits == 6 { 3 } else { 4 };
+    let mask = (1 << bits) - 1;
+
+    let mut rgba = [1.0; 4];
+    for (i, channel) in rgba.iter_mut().take(count).enumerate() {
+        let shift = bits * (count - 1 - i);
+        *channel = ((value >> shift) & mask) as f32 / scale;
+    }
+    return rgba;
+}
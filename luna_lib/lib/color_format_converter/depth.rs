@@ -0,0 +1,120 @@
+//! Channel types other than `u8`, and conversion between channel bit depths.
+
+use super::ColorFormatConverterError;
+
+/// A type that can hold the value of a single color channel.
+///
+/// Implemented for `u8` (`0-255`), `u16` (`0-65535`) and `f32` (`0.0-1.0`), which covers 8-bit and 16-bit images
+/// as well as `HDR`/`EXR` float data, as decoded by the `image` crate (e.g. `ImageBuffer::into_raw()`).
+/// Values are scaled between types through their normalized `0.0-1.0` value, so the maximum of one type
+/// always maps to the maximum of the other.
+pub trait ChannelType: Copy + PartialEq + std::fmt::Debug {
+    /// The value of a fully saturated channel, used e.g. for a missing alpha channel.
+    const MAX: Self;
+    /// Whether the type is a floating-point type, meaning it can hold values outside of its normal range.
+    const IS_FLOAT: bool;
+
+    /// Returns the channel value normalized to `0.0-1.0`.
+    fn to_normalized(self) -> f32;
+
+    /// Creates a channel value from a normalized `0.0-1.0` value.
+    /// Integer types round to the nearest value and clamp out-of-range inputs, `NaN` becomes `0`.
+    fn from_normalized(value: f32) -> Self;
+
+    /// Checks that the value is a valid channel value.
+    /// This always succeeds for integer types, while floats must not be `NaN` and must be within `0.0-1.0`.
+    fn check(self) -> Result<(), ColorFormatConverterError> {
+        return Ok(());
+    }
+}
+
+impl ChannelType for u8 {
+    const MAX: Self = u8::MAX;
+    const IS_FLOAT: bool = false;
+
+    #[inline]
+    fn to_normalized(self) -> f32 {
+        return self as f32 / 255.0;
+    }
+
+    #[inline]
+    fn from_normalized(value: f32) -> Self {
+        return (value * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+impl ChannelType for u16 {
+    const MAX: Self = u16::MAX;
+    const IS_FLOAT: bool = false;
+
+    #[inline]
+    fn to_normalized(self) -> f32 {
+        return self as f32 / 65535.0;
+    }
+
+    #[inline]
+    fn from_normalized(value: f32) -> Self {
+        return (value * 65535.0).round().clamp(0.0, 65535.0) as u16;
+    }
+}
+
+impl ChannelType for f32 {
+    const MAX: Self = 1.0;
+    const IS_FLOAT: bool = true;
+
+    #[inline]
+    fn to_normalized(self) -> f32 {
+        return self;
+    }
+
+    #[inline]
+    fn from_normalized(value: f32) -> Self {
+        return value;
+    }
+
+    fn check(self) -> Result<(), ColorFormatConverterError> {
+        if self.is_nan() { return Err(ColorFormatConverterError::NotANumber); }
+        if !(0.0..=1.0).contains(&self) { return Err(ColorFormatConverterError::OutOfRange); }
+        return Ok(());
+    }
+}
+
+/// Convert channel values from one channel type to another, e.g. from 16-bit to 8-bit.
+///
+/// The values are scaled so that the full range of one type maps to the full range of the other,
+/// e.g. `255u8` becomes `65535u16` and `1.0f32`. Integer results are rounded to the nearest value.
+/// Since the conversion is per channel, it works for any [`ColorFormat`](super::ColorFormat).
+///
+/// ## Parameters
+/// - `data`: input channel values, of any [`ChannelType`]
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<U>` of the same length as `data`, or a [`ColorFormatConverterError`]:
+/// `NotANumber` if a float input is `NaN`, or `OutOfRange` if a float input is outside of `0.0-1.0`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_channel_depth, ColorFormatConverterError};
+///
+/// let rgb8: Vec<u8> = vec![0, 128, 255];
+/// let rgb16: Vec<u16> = convert_channel_depth(&rgb8).unwrap();
+/// assert_eq!(rgb16, vec![0, 32896, 65535]);
+///
+/// let back: Vec<u8> = convert_channel_depth(&rgb16).unwrap();
+/// assert_eq!(back, rgb8);
+///
+/// let hdr: Vec<f32> = vec![0.0, 0.5, 1.0];
+/// assert_eq!(convert_channel_depth::<f32, u8>(&hdr), Ok(vec![0, 128, 255]));
+///
+/// // NaN and out of range floats are rejected
+/// assert_eq!(convert_channel_depth::<f32, u8>(&[f32::NAN]), Err(ColorFormatConverterError::NotANumber));
+/// assert_eq!(convert_channel_depth::<f32, u16>(&[1.5]), Err(ColorFormatConverterError::OutOfRange));
+/// ```
+pub fn convert_channel_depth<T: ChannelType, U: ChannelType>(data: &[T]) -> Result<Vec<U>, ColorFormatConverterError> {
+    let mut out = Vec::with_capacity(data.len());
+    for &value in data {
+        value.check()?;
+        out.push(U::from_normalized(value.to_normalized()));
+    }
+    return Ok(out);
+}
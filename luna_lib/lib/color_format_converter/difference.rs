@@ -0,0 +1,204 @@
+//! Color difference metrics, measuring how far apart two colors look.
+//!
+//! All metrics take two single pixels in any [`ColorFormat`], so e.g. a `CMYK` print color can be compared
+//! directly with an `RGB` screen color. Alpha is ignored.
+
+use super::models::{decode_lab, decode_oklab};
+use super::{ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// Calculate the `CIE76` color difference (`ΔE*ab`), the plain distance between two colors in `CIE L*a*b*`.
+///
+/// It is the simplest and fastest metric, but overestimates differences between saturated colors.
+/// A difference of about `2.3` is considered just noticeable.
+///
+/// ## Parameters
+/// - `a`: the first color, a single pixel of `a_format`
+/// - `a_format`: color model of `a`
+/// - `b`: the second color, a single pixel of `b_format`
+/// - `b_format`: color model of `b`
+///
+/// ## Returns
+/// A `Result` containing either the difference as an `f32`, or a [`ColorFormatConverterError`] if a pixel
+/// doesn't have exactly one pixel's worth of channels, or can't be decoded (see [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic)).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{delta_e_76, ColorFormat};
+///
+/// // The same red, as RGB and as CMYK
+/// let rgb = [255u8, 0, 0];
+/// let cmyk = [0u8, 255, 255, 0];
+/// assert!(delta_e_76(&rgb, ColorFormat::RGB, &cmyk, ColorFormat::CMYK).unwrap() < 0.01);
+///
+/// // Black and white are 100 apart
+/// let black = [0u8, 0, 0];
+/// let white = [255u8];
+/// assert!((delta_e_76(&black, ColorFormat::RGB, &white, ColorFormat::Gray).unwrap() - 100.0).abs() < 0.01);
+/// ```
+pub fn delta_e_76<A: ChannelType, B: ChannelType>(a: &[A], a_format: ColorFormat, b: &[B], b_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let lab1 = decode_lab(a, a_format)?;
+    let lab2 = decode_lab(b, b_format)?;
+    return Ok(distance(lab1, lab2));
+}
+
+/// Calculate the `CIE94` color difference (`ΔE*94`), using the graphic arts weights.
+///
+/// It corrects most of the overestimation of [`delta_e_76`] for saturated colors.
+/// Note that this metric isn't symmetric: `a` is the reference color, and `b` the sample being compared to it.
+///
+/// ## Parameters
+/// - `a`: the reference color, a single pixel of `a_format`
+/// - `a_format`: color model of `a`
+/// - `b`: the sample color, a single pixel of `b_format`
+/// - `b_format`: color model of `b`
+///
+/// ## Returns
+/// A `Result` containing either the difference as an `f32`, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{delta_e_94, ColorFormat};
+///
+/// let lab1 = [50.0f32, 80.0, 0.0];
+/// let lab2 = [50.0f32, 70.0, 0.0];
+/// let de94 = delta_e_94(&lab1, ColorFormat::Lab, &lab2, ColorFormat::Lab).unwrap();
+/// assert!((de94 - 10.0 / 4.6).abs() < 0.001); // the chroma difference is weighted down by 1 + 0.045 * 80
+/// ```
+pub fn delta_e_94<A: ChannelType, B: ChannelType>(a: &[A], a_format: ColorFormat, b: &[B], b_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let [l1, a1, b1] = decode_lab(a, a_format)?;
+    let [l2, a2, b2] = decode_lab(b, b_format)?;
+
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+
+    let delta_l = l1 - l2;
+    let delta_c = c1 - c2;
+    let delta_h_sq = ((a1 - a2).powi(2) + (b1 - b2).powi(2) - delta_c * delta_c).max(0.0);
+
+    let sc = 1.0 + 0.045 * c1;
+    let sh = 1.0 + 0.015 * c1;
+
+    return Ok((delta_l.powi(2) + (delta_c / sc).powi(2) + delta_h_sq / (sh * sh)).sqrt());
+}
+
+/// Calculate the `CIEDE2000` color difference (`ΔE00`), the most accurate of the `CIE` metrics.
+///
+/// It corrects for the perceptual non-uniformity of `CIE L*a*b*` in lightness, chroma and hue, and for the
+/// blue region in particular. A difference below `1.0` is generally not noticeable.
+///
+/// ## Parameters
+/// - `a`: the first color, a single pixel of `a_format`
+/// - `a_format`: color model of `a`
+/// - `b`: the second color, a single pixel of `b_format`
+/// - `b_format`: color model of `b`
+///
+/// ## Returns
+/// A `Result` containing either the difference as an `f32`, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{delta_e_2000, ColorFormat};
+///
+/// // Test data from Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference Formula"
+/// let lab1 = [50.0f32, 2.6772, -79.7751];
+/// let lab2 = [50.0f32, 0.0, -82.7485];
+/// let de00 = delta_e_2000(&lab1, ColorFormat::Lab, &lab2, ColorFormat::Lab).unwrap();
+/// assert!((de00 - 2.0425).abs() < 0.0001);
+///
+/// // Brand color check: print vs screen
+/// let print = [0u8, 46, 232, 18];     // CMYK
+/// let screen = [236u8, 196, 22];      // RGB
+/// let de00 = delta_e_2000(&print, ColorFormat::CMYK, &screen, ColorFormat::RGB).unwrap();
+/// assert!(de00 < 2.0);
+/// ```
+pub fn delta_e_2000<A: ChannelType, B: ChannelType>(a: &[A], a_format: ColorFormat, b: &[B], b_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let lab1 = decode_lab(a, a_format)?;
+    let lab2 = decode_lab(b, b_format)?;
+    return Ok(ciede2000(lab1, lab2));
+}
+
+/// Calculate the distance between two colors in `OKLab`.
+///
+/// `OKLab` is much more perceptually uniform than `CIE L*a*b*`, so its plain Euclidean distance is a good and cheap
+/// metric on its own. Since `OKLab` lightness is in `0-1`, differences are about 100 times smaller than the `CIE` metrics,
+/// with about `0.02` being just noticeable.
+///
+/// ## Parameters
+/// - `a`: the first color, a single pixel of `a_format`
+/// - `a_format`: color model of `a`
+/// - `b`: the second color, a single pixel of `b_format`
+/// - `b_format`: color model of `b`
+///
+/// ## Returns
+/// A `Result` containing either the difference as an `f32`, or a [`ColorFormatConverterError`] if a pixel can't be decoded.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{delta_e_ok, ColorFormat};
+///
+/// let black = [0u8, 0, 0];
+/// let white = [255u8, 255, 255, 255];
+/// assert!((delta_e_ok(&black, ColorFormat::RGB, &white, ColorFormat::BGRA).unwrap() - 1.0).abs() < 0.001);
+/// ```
+pub fn delta_e_ok<A: ChannelType, B: ChannelType>(a: &[A], a_format: ColorFormat, b: &[B], b_format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let lab1 = decode_oklab(a, a_format)?;
+    let lab2 = decode_oklab(b, b_format)?;
+    return Ok(distance(lab1, lab2));
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    return ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+}
+
+/// `CIEDE2000` between two `CIE L*a*b*` colors, calculated in `f64` to stay accurate near hue discontinuities.
+fn ciede2000(lab1: [f32; 3], lab2: [f32; 3]) -> f32 {
+    let [l1, a1, b1] = lab1.map(|v| v as f64);
+    let [l2, a2, b2] = lab2.map(|v| v as f64);
+    let pow7_25 = 25f64.powi(7);
+
+    let c_mean = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2.0;
+    let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + pow7_25)).sqrt());
+
+    let a1p = (1.0 + g) * a1;
+    let a2p = (1.0 + g) * a2;
+    let c1p = (a1p * a1p + b1 * b1).sqrt();
+    let c2p = (a2p * a2p + b2 * b2).sqrt();
+
+    let hue = |b: f64, a: f64| if a == 0.0 && b == 0.0 { 0.0 } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+    let h1p = hue(b1, a1p);
+    let h2p = hue(b2, a2p);
+
+    let delta_lp = l2 - l1;
+    let delta_cp = c2p - c1p;
+    let delta_hp = if c1p * c2p == 0.0 { 0.0 }
+        else if (h2p - h1p).abs() <= 180.0 { h2p - h1p }
+        else if h2p - h1p > 180.0 { h2p - h1p - 360.0 }
+        else { h2p - h1p + 360.0 };
+    let delta_big_hp = 2.0 * (c1p * c2p).sqrt() * (delta_hp.to_radians() / 2.0).sin();
+
+    let l_mean = (l1 + l2) / 2.0;
+    let cp_mean = (c1p + c2p) / 2.0;
+    let hp_mean = if c1p * c2p == 0.0 { h1p + h2p }
+        else if (h1p - h2p).abs() <= 180.0 { (h1p + h2p) / 2.0 }
+        else if h1p + h2p < 360.0 { (h1p + h2p + 360.0) / 2.0 }
+        else { (h1p + h2p - 360.0) / 2.0 };
+
+    let t = 1.0
+        - 0.17 * (hp_mean - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * hp_mean).to_radians().cos()
+        + 0.32 * (3.0 * hp_mean + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * hp_mean - 63.0).to_radians().cos();
+
+    let delta_theta = 30.0 * (-((hp_mean - 275.0) / 25.0).powi(2)).exp();
+    let rc = 2.0 * (cp_mean.powi(7) / (cp_mean.powi(7) + pow7_25)).sqrt();
+    let sl = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+    let sc = 1.0 + 0.045 * cp_mean;
+    let sh = 1.0 + 0.015 * cp_mean * t;
+    let rt = -(2.0 * delta_theta).to_radians().sin() * rc;
+
+    let l_term = delta_lp / sl;
+    let c_term = delta_cp / sc;
+    let h_term = delta_big_hp / sh;
+
+    return (l_term * l_term + c_term * c_term + h_term * h_term + rt * c_term * h_term).sqrt() as f32;
+}
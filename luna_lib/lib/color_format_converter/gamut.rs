@@ -0,0 +1,345 @@
+//! Wide-gamut `RGB` spaces, gamut checking and gamut mapping.
+//!
+//! Colors from `CIE L*a*b*`, `OKLCH` or a wide-gamut space may have no `sRGB` equivalent, which shows as channel values
+//! outside of `0.0-1.0`. Gamut mapping brings such colors into a space's gamut, ideally without changing how they look more than needed.
+
+use std::sync::OnceLock;
+
+use super::models::map_colors;
+use super::spaces::{linear_to_srgb, mul, srgb_to_linear};
+use super::{ChannelType, Color, ColorFormat, ColorFormatConverterError};
+
+/// Channel values may be this far outside of `0.0-1.0` and still count as in gamut, to allow for rounding errors.
+const GAMUT_EPSILON: f32 = 0.0001;
+
+/// The `OKLab` distance below which colors look the same, used by the `CSS Color 4` gamut mapping.
+const JND: f32 = 0.02;
+
+type Matrix = [[f32; 3]; 3];
+
+/// `RGB` color spaces, all relative to the `D65` white point.
+///
+/// - `SRGB`: The standard space of the web and most displays and images.
+/// - `DisplayP3`: The space of wide-gamut Apple and many newer displays, `DCI-P3` primaries with the `sRGB` transfer function.
+/// - `Rec2020`: The space of UHD and HDR video (`BT.2020`), with much more saturated primaries than any common display.
+/// - `AdobeRGB`: Adobe RGB (1998), a space for photography and print with more saturated greens and cyans.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RgbSpace {
+    SRGB,
+    DisplayP3,
+    Rec2020,
+    AdobeRGB,
+}
+
+impl RgbSpace {
+    /// The `CIE 1931 xy` chromaticities of the red, green and blue primaries.
+    pub fn primaries(&self) -> [(f32, f32); 3] {
+        return match self {
+            RgbSpace::SRGB => [(0.640, 0.330), (0.300, 0.600), (0.150, 0.060)],
+            RgbSpace::DisplayP3 => [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            RgbSpace::Rec2020 => [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)],
+            RgbSpace::AdobeRGB => [(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)],
+        };
+    }
+
+    /// The `CIE 1931 xy` chromaticity of the white point, `D65` for all spaces.
+    pub fn white_point(&self) -> (f32, f32) {
+        return (0.3127, 0.3290);
+    }
+
+    /// Decodes an encoded channel value into linear light, mirrored around `0.0` like [`TransferFunction`](super::TransferFunction).
+    pub fn decode(&self, value: f32) -> f32 {
+        let v = value.abs();
+        let linear = match self {
+            RgbSpace::SRGB | RgbSpace::DisplayP3 => return super::TransferFunction::SRGB.decode(value),
+            RgbSpace::Rec2020 => if v < REC2020_BETA * 4.5 { v / 4.5 } else { ((v + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45) },
+            RgbSpace::AdobeRGB => v.powf(ADOBE_GAMMA),
+        };
+        return linear.copysign(value);
+    }
+
+    /// Encodes linear light into a channel value, mirrored around `0.0` like [`TransferFunction`](super::TransferFunction).
+    pub fn encode(&self, value: f32) -> f32 {
+        let l = value.abs();
+        let encoded = match self {
+            RgbSpace::SRGB | RgbSpace::DisplayP3 => return super::TransferFunction::SRGB.encode(value),
+            RgbSpace::Rec2020 => if l < REC2020_BETA { l * 4.5 } else { REC2020_ALPHA * l.powf(0.45) - (REC2020_ALPHA - 1.0) },
+            RgbSpace::AdobeRGB => l.powf(1.0 / ADOBE_GAMMA),
+        };
+        return encoded.copysign(value);
+    }
+
+    /// The matrices from linear values of the space to `XYZ`, and back.
+    fn matrices(&self) -> &'static (Matrix, Matrix) {
+        static MATRICES: OnceLock<[(Matrix, Matrix); 4]> = OnceLock::new();
+        let all = MATRICES.get_or_init(|| {
+            [RgbSpace::SRGB, RgbSpace::DisplayP3, RgbSpace::Rec2020, RgbSpace::AdobeRGB].map(|space| {
+                let to_xyz = rgb_to_xyz_matrix(space.primaries(), space.white_point());
+                (to_f32(to_xyz), to_f32(invert(to_xyz)))
+            })
+        });
+        return &all[*self as usize];
+    }
+}
+
+const REC2020_ALPHA: f32 = 1.099_296_8;
+const REC2020_BETA: f32 = 0.018_053_97;
+const ADOBE_GAMMA: f32 = 563.0 / 256.0;
+
+/// Convert a color to the encoded channel values of an `RGB` space, in `0.0-1.0` if the color is in the space's gamut.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{to_rgb_space, Color, RgbSpace};
+///
+/// let [r, g, b] = to_rgb_space(Color::from_rgb8(255, 0, 0), RgbSpace::DisplayP3);
+/// assert!((r - 0.9175).abs() < 0.001 && (g - 0.2003).abs() < 0.001 && (b - 0.1386).abs() < 0.001);
+/// ```
+pub fn to_rgb_space(color: Color, space: RgbSpace) -> [f32; 3] {
+    let rgb = [color.r, color.g, color.b];
+    if space == RgbSpace::SRGB {
+        return rgb;
+    }
+    // the sRGB matrices are derived the same way as those of the space, so white stays exactly white
+    let xyz = mul(&RgbSpace::SRGB.matrices().0, srgb_to_linear(rgb));
+    let linear = mul(&space.matrices().1, xyz);
+    return linear.map(|c| space.encode(c));
+}
+
+/// Convert a color to the encoded channel values of an `RGB` space, failing if it's out of the space's gamut.
+///
+/// ## Returns
+/// A `Result` containing either the channel values in `0.0-1.0`, or `OutOfRange` if the color is out of gamut, see [`is_in_gamut`].
+pub fn to_rgb_space_checked(color: Color, space: RgbSpace) -> Result<[f32; 3], ColorFormatConverterError> {
+    if !is_in_gamut(color, space) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    return Ok(to_rgb_space(color, space).map(|c| c.clamp(0.0, 1.0)));
+}
+
+/// Create a color from the encoded channel values of an `RGB` space.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{from_rgb_space, Color, RgbSpace};
+///
+/// // Display P3 red is more saturated than any sRGB color
+/// let red = from_rgb_space([1.0, 0.0, 0.0], RgbSpace::DisplayP3, 1.0);
+/// assert!(red.r > 1.0 && red.g < 0.0 && red.b < 0.0);
+/// assert_eq!(red, "color(display-p3 1 0 0)".parse().unwrap());
+/// ```
+pub fn from_rgb_space(rgb: [f32; 3], space: RgbSpace, alpha: f32) -> Color {
+    if space == RgbSpace::SRGB {
+        return Color::new(rgb[0], rgb[1], rgb[2], alpha);
+    }
+    let xyz = mul(&space.matrices().0, rgb.map(|c| space.decode(c)));
+    let [r, g, b] = linear_to_srgb(mul(&RgbSpace::SRGB.matrices().1, xyz));
+    return Color::new(r, g, b, alpha);
+}
+
+/// Check whether a color can be shown in an `RGB` space, meaning all its channels in that space are within `0.0-1.0`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{is_in_gamut, Color, RgbSpace};
+///
+/// let rec2020_green: Color = "color(rec2020 0 1 0)".parse().unwrap();
+/// assert!(!is_in_gamut(rec2020_green, RgbSpace::SRGB));
+/// assert!(!is_in_gamut(rec2020_green, RgbSpace::DisplayP3));
+/// assert!(is_in_gamut(rec2020_green, RgbSpace::Rec2020));
+///
+/// assert!(is_in_gamut(Color::from_rgb8(255, 255, 255), RgbSpace::AdobeRGB));
+/// ```
+pub fn is_in_gamut(color: Color, space: RgbSpace) -> bool {
+    return in_gamut(to_rgb_space(color, space));
+}
+
+/// How colors outside of a gamut are brought into it.
+///
+/// - `Clip`: Clamps every channel to `0.0-1.0`. Fast, but can shift hue and lightness noticeably.
+/// - `Chroma`: Reduces the chroma in `OKLCH`, keeping lightness and hue, as specified by `CSS Color 4`. The best looking, but slowest.
+/// - `Scale`: Moves the color towards the gray of the same luminance in linear light until it fits, keeping luminance and roughly hue.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GamutMapping {
+    Clip,
+    Chroma,
+    Scale,
+}
+
+/// Bring a color into the gamut of an `RGB` space. Colors that are already in gamut are returned as is.
+///
+/// ## Parameters
+/// - `color`: the color to map
+/// - `space`: the space whose gamut the result must fit
+/// - `method`: how to bring the color into gamut
+///
+/// ## Returns
+/// The mapped color, which [`to_rgb_space`] converts to values within `0.0-1.0`. Alpha is kept.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{gamut_map, is_in_gamut, Color, GamutMapping, RgbSpace};
+///
+/// let p3_red: Color = "color(display-p3 1 0 0)".parse().unwrap();
+///
+/// let clipped = gamut_map(p3_red, RgbSpace::SRGB, GamutMapping::Clip);
+/// assert_eq!(clipped.to_hex(), "#ff0000");
+///
+/// let mapped = gamut_map(p3_red, RgbSpace::SRGB, GamutMapping::Chroma);
+/// assert!(is_in_gamut(mapped, RgbSpace::SRGB));
+/// assert_eq!(mapped.to_hex(), "#ff0b0c");
+/// assert!((mapped.to_oklch()[0] - p3_red.to_oklch()[0]).abs() < 0.02); // about the same lightness
+/// ```
+pub fn gamut_map(color: Color, space: RgbSpace, method: GamutMapping) -> Color {
+    if is_in_gamut(color, space) {
+        return color;
+    }
+    return match method {
+        GamutMapping::Clip => clip(color, space),
+        GamutMapping::Chroma => map_chroma(color, space),
+        GamutMapping::Scale => scale(color, space),
+    };
+}
+
+/// Bring every pixel of a buffer into the gamut of an `RGB` space, see [`gamut_map`].
+///
+/// This is mostly useful for float buffers of unbounded formats such as `OKLCh` or `Lab`, before converting them to an `RGB` format.
+///
+/// ## Parameters
+/// - `data`: input slice of pixels, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`
+/// - `space`: the space whose gamut the result must fit
+/// - `method`: how to bring colors into gamut
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format, or any of the errors of [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_vec_color_model_generic, gamut_map_colors, ColorFormat, GamutMapping, RgbSpace};
+///
+/// // A vivid green that sRGB can't show
+/// let oklch: Vec<f32> = vec![0.85, 0.3, 145.0];
+/// let mapped = gamut_map_colors(&oklch, ColorFormat::OKLCh, RgbSpace::SRGB, GamutMapping::Chroma).unwrap();
+/// assert!(mapped[1] < 0.3);
+///
+/// let rgb = convert_vec_color_model_generic(&mapped, ColorFormat::OKLCh, ColorFormat::RGB).unwrap();
+/// assert!(rgb.iter().all(|c| (-0.001..=1.001).contains(c)));
+/// ```
+pub fn gamut_map_colors<T: ChannelType>(
+    data: &[T],
+    format: ColorFormat,
+    space: RgbSpace,
+    method: GamutMapping
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    return map_colors(data, format, |[r, g, b, a]| {
+        let mapped = gamut_map(Color::new(r, g, b, a), space, method);
+        [mapped.r, mapped.g, mapped.b, mapped.a]
+    });
+}
+
+fn in_gamut(rgb: [f32; 3]) -> bool {
+    return rgb.iter().all(|c| (-GAMUT_EPSILON..=1.0 + GAMUT_EPSILON).contains(c));
+}
+
+fn clip(color: Color, space: RgbSpace) -> Color {
+    return from_rgb_space(to_rgb_space(color, space).map(|c| c.clamp(0.0, 1.0)), space, color.a);
+}
+
+/// The gamut mapping algorithm of `CSS Color 4`: a binary search for the highest `OKLCH` chroma whose clipped color
+/// is indistinguishable from it.
+fn map_chroma(color: Color, space: RgbSpace) -> Color {
+    let [l, c, h] = color.to_oklch();
+    if l >= 1.0 {
+        return from_rgb_space([1.0; 3], space, color.a);
+    }
+    if l <= 0.0 {
+        return from_rgb_space([0.0; 3], space, color.a);
+    }
+
+    let distance = |a: Color, b: Color| {
+        let (a, b) = (a.to_oklab(), b.to_oklab());
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    };
+    let mut clipped = clip(color, space);
+    if distance(clipped, color) < JND {
+        return clipped;
+    }
+
+    let (mut min, mut max) = (0.0, c);
+    let mut min_in_gamut = true;
+    while max - min > GAMUT_EPSILON {
+        let chroma = (min + max) / 2.0;
+        let current = Color::from_oklch(l, chroma, h, color.a);
+        if min_in_gamut && is_in_gamut(current, space) {
+            min = chroma;
+            continue;
+        }
+        clipped = clip(current, space);
+        let error = distance(clipped, current);
+        if error < JND {
+            if JND - error < GAMUT_EPSILON {
+                return clipped;
+            }
+            min_in_gamut = false;
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+    return clipped;
+}
+
+/// Moves the color towards the gray of the same luminance in the linear light of the space, until it fits.
+fn scale(color: Color, space: RgbSpace) -> Color {
+    let linear = to_rgb_space(color, space).map(|c| space.decode(c));
+    let to_xyz = &space.matrices().0;
+    let y = to_xyz[1][0] * linear[0] + to_xyz[1][1] * linear[1] + to_xyz[1][2] * linear[2];
+    if y >= 1.0 {
+        return from_rgb_space([1.0; 3], space, color.a);
+    }
+    if y <= 0.0 {
+        return from_rgb_space([0.0; 3], space, color.a);
+    }
+
+    // the largest fraction of the distance from gray that keeps every channel in range
+    let t = linear.iter().fold(1.0f32, |t, &c| {
+        if c > 1.0 { t.min((1.0 - y) / (c - y)) }
+        else if c < 0.0 { t.min(y / (y - c)) }
+        else { t }
+    });
+    let scaled = linear.map(|c| (y + (c - y) * t).clamp(0.0, 1.0));
+    return from_rgb_space(scaled.map(|c| space.encode(c)), space, color.a);
+}
+
+/// The matrix from linear `RGB` to `XYZ` of a space with the given primaries and white point.
+fn rgb_to_xyz_matrix(primaries: [(f32, f32); 3], white: (f32, f32)) -> [[f64; 3]; 3] {
+    let xyz = |(x, y): (f32, f32)| {
+        let (x, y) = (x as f64, y as f64);
+        [x / y, 1.0, (1.0 - x - y) / y]
+    };
+    let columns = primaries.map(xyz);
+    let p: [[f64; 3]; 3] = std::array::from_fn(|row| std::array::from_fn(|col| columns[col][row]));
+
+    // scale the primaries so that they add up to the white point
+    let w = xyz(white);
+    let inverse = invert(p);
+    let s: [f64; 3] = std::array::from_fn(|row| (0..3).map(|col| inverse[row][col] * w[col]).sum());
+    return std::array::from_fn(|row| std::array::from_fn(|col| p[row][col] * s[col]));
+}
+
+fn invert(m: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+        [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+        [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+    ];
+    let determinant = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    return adjugate.map(|row| row.map(|v| v / determinant));
+}
+
+fn to_f32(m: [[f64; 3]; 3]) -> Matrix {
+    return m.map(|row| row.map(|v| v as f32));
+}
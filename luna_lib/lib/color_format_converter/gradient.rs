@@ -0,0 +1,217 @@
+//! Gradients between any number of color stops, interpolated in a chosen color space.
+
+use super::spaces::{linear_to_srgb, srgb_to_linear};
+use super::{ChannelType, Color, ColorFormat, ColorFormatConverterError};
+
+/// Which way around the color wheel hues are interpolated, following `CSS Color 4`.
+///
+/// - `Shorter`: The shortest way, never more than `180°`.
+/// - `Longer`: The longest way, never less than `180°`.
+/// - `Increasing`: Always with increasing hue, e.g. from red through green to blue.
+/// - `Decreasing`: Always with decreasing hue, e.g. from blue through green to red.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HuePath {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+/// The color space in which a gradient is interpolated.
+///
+/// - `SRGB`: Gamma-encoded `sRGB`, as most software has traditionally done. Mixes of complementary colors turn dark and muddy.
+/// - `LinearRGB`: Linear-light `sRGB`, physically correct mixing of light. Dark colors take up little of the gradient.
+/// - `HSL`: Hue, saturation and lightness, going around the color wheel along the given path.
+/// - `OKLab`: Perceptually uniform `OKLab`, giving even steps without hue shifts.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum InterpolationSpace {
+    SRGB,
+    LinearRGB,
+    HSL(HuePath),
+    OKLab,
+}
+
+/// A gradient between color stops, sampled at positions usually in `0.0-1.0`.
+///
+/// Stops are interpolated with premultiplied alpha, so fading to a transparent color doesn't pass through the
+/// transparent color's (invisible) hue. Before the first stop and after the last stop, the gradient keeps the color of that stop.
+/// Two stops at the same position make a hard edge.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{Color, ColorFormat, Gradient, HuePath, InterpolationSpace};
+///
+/// let red: Color = "red".parse().unwrap();
+/// let blue: Color = "blue".parse().unwrap();
+///
+/// let gradient = Gradient::evenly_spaced(&[red, blue]).unwrap();
+/// assert_eq!(gradient.sample(0.5).to_hex(), "#8c53a2"); // OKLab by default
+///
+/// let gradient = gradient.with_space(InterpolationSpace::SRGB);
+/// assert_eq!(gradient.sample(0.5).to_hex(), "#800080");
+///
+/// let gradient = gradient.with_space(InterpolationSpace::HSL(HuePath::Increasing));
+/// assert_eq!(gradient.sample(0.5).to_hex(), "#00ff00"); // red, through green, to blue
+///
+/// // A 3 pixel wide RGBA row
+/// let row = gradient.render::<u8>(3, ColorFormat::RGBA).unwrap();
+/// assert_eq!(row, vec![255, 0, 0, 255,  0, 255, 0, 255,  0, 0, 255, 255]);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Gradient {
+    stops: Vec<(f32, Color)>,
+    space: InterpolationSpace,
+}
+
+impl Gradient {
+    /// Creates a new gradient from color stops, as `(position, color)` pairs, interpolated in `OKLab`.
+    ///
+    /// ## Returns
+    /// A `Result` containing either the gradient, or a [`ColorFormatConverterError`]: `InvalidInputLength` if there are no stops,
+    /// `NotANumber` if a position is `NaN`, or `OutOfRange` if the positions aren't in increasing order.
+    pub fn new(stops: &[(f32, Color)]) -> Result<Self, ColorFormatConverterError> {
+        if stops.is_empty() {
+            return Err(ColorFormatConverterError::InvalidInputLength);
+        }
+        if stops.iter().any(|(position, _)| position.is_nan()) {
+            return Err(ColorFormatConverterError::NotANumber);
+        }
+        if stops.windows(2).any(|w| w[1].0 < w[0].0) {
+            return Err(ColorFormatConverterError::OutOfRange);
+        }
+        return Ok(Self { stops: stops.to_vec(), space: InterpolationSpace::OKLab });
+    }
+
+    /// Creates a new gradient with the colors evenly spaced from `0.0` to `1.0`, interpolated in `OKLab`.
+    ///
+    /// ## Returns
+    /// A `Result` containing either the gradient, or `InvalidInputLength` if there are no colors.
+    pub fn evenly_spaced(colors: &[Color]) -> Result<Self, ColorFormatConverterError> {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        let stops: Vec<(f32, Color)> = colors.iter().enumerate().map(|(i, &c)| (i as f32 / last, c)).collect();
+        return Self::new(&stops);
+    }
+
+    /// Returns the gradient, interpolated in the given color space instead.
+    pub fn with_space(mut self, space: InterpolationSpace) -> Self {
+        self.space = space;
+        return self;
+    }
+
+    /// The color stops of the gradient, as `(position, color)` pairs.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        return &self.stops;
+    }
+
+    /// The color space the gradient is interpolated in.
+    pub fn space(&self) -> InterpolationSpace {
+        return self.space;
+    }
+
+    /// Samples the color of the gradient at position `t`.
+    pub fn sample(&self, t: f32) -> Color {
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if t.is_nan() || t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        // the first stop past `t`, so a hard edge (two stops at the same position) switches to the later color
+        let next = self.stops.iter().position(|(position, _)| *position > t).unwrap_or(self.stops.len() - 1);
+        let (start, from) = self.stops[next - 1];
+        let (end, to) = self.stops[next];
+        return interpolate(from, to, (t - start) / (end - start), self.space);
+    }
+
+    /// Renders the gradient into a row of pixels, sampled evenly from `0.0` to `1.0`, both included.
+    ///
+    /// ## Parameters
+    /// - `width`: the amount of pixels in the row
+    /// - `format`: the color model of the output, e.g. `RGBA` for images
+    ///
+    /// ## Returns
+    /// A `Result` containing either a new `Vec` of `width` pixels, or a [`ColorFormatConverterError`]: `InvalidDimensions` if `width` is `0`,
+    /// or `UnsupportedFormat` for formats the channel type can't hold.
+    pub fn render<T: ChannelType>(&self, width: usize, format: ColorFormat) -> Result<Vec<T>, ColorFormatConverterError> {
+        if width == 0 {
+            return Err(ColorFormatConverterError::InvalidDimensions);
+        }
+        let last = (width - 1).max(1) as f32;
+        let mut out = Vec::with_capacity(width * format.bytes_per_pixel());
+        for x in 0..width {
+            out.extend(self.sample(x as f32 / last).to_pixel::<T>(format)?);
+        }
+        return Ok(out);
+    }
+}
+
+/// Interpolates between two colors in the given space with premultiplied alpha, where `t` of `0.0` is `from` and `1.0` is `to`.
+fn interpolate(from: Color, to: Color, t: f32, space: InterpolationSpace) -> Color {
+    let alpha = from.a + (to.a - from.a) * t;
+    let mut a = components(from, space);
+    let mut b = components(to, space);
+
+    // in HSL, the hue is the first component, and isn't premultiplied
+    let hue = matches!(space, InterpolationSpace::HSL(_));
+    let first = if hue { 1 } else { 0 };
+    for (a, b) in a.iter_mut().zip(b.iter_mut()).skip(first) {
+        *a *= from.a;
+        *b *= to.a;
+    }
+
+    let mut mixed: [f32; 3] = std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t);
+    if let InterpolationSpace::HSL(path) = space {
+        mixed[0] = interpolate_hue(a[0], b[0], a[1] <= 0.0, b[1] <= 0.0, t, path);
+    }
+    if alpha > 0.0 {
+        for value in mixed.iter_mut().skip(first) {
+            *value /= alpha;
+        }
+    }
+
+    return match space {
+        InterpolationSpace::SRGB => Color::new(mixed[0], mixed[1], mixed[2], alpha),
+        InterpolationSpace::LinearRGB => {
+            let [r, g, b] = linear_to_srgb(mixed);
+            Color::new(r, g, b, alpha)
+        },
+        InterpolationSpace::HSL(_) => Color::from_hsl(mixed[0], mixed[1], mixed[2], alpha),
+        InterpolationSpace::OKLab => Color::from_oklab(mixed[0], mixed[1], mixed[2], alpha),
+    };
+}
+
+fn components(color: Color, space: InterpolationSpace) -> [f32; 3] {
+    return match space {
+        InterpolationSpace::SRGB => [color.r, color.g, color.b],
+        InterpolationSpace::LinearRGB => srgb_to_linear([color.r, color.g, color.b]),
+        InterpolationSpace::HSL(_) => color.to_hsl(),
+        InterpolationSpace::OKLab => color.to_oklab(),
+    };
+}
+
+/// Interpolates between two hues in degrees along the given path.
+/// The hue of a gray color is meaningless, so it takes the hue of the other color instead.
+fn interpolate_hue(from: f32, to: f32, from_gray: bool, to_gray: bool, t: f32, path: HuePath) -> f32 {
+    let (mut from, mut to) = match (from_gray, to_gray) {
+        (true, false) => (to, to),
+        (false, true) => (from, from),
+        _ => (from, to),
+    };
+
+    match path {
+        HuePath::Shorter => {
+            if to - from > 180.0 { from += 360.0; }
+            else if to - from < -180.0 { to += 360.0; }
+        },
+        HuePath::Longer => {
+            if 0.0 < to - from && to - from < 180.0 { from += 360.0; }
+            else if -180.0 < to - from && to - from <= 0.0 && to != from { to += 360.0; }
+        },
+        HuePath::Increasing => if to < from { to += 360.0; },
+        HuePath::Decreasing => if from < to { from += 360.0; },
+    }
+    return (from + (to - from) * t).rem_euclid(360.0);
+}
@@ -0,0 +1,506 @@
+//! Buffer layouts other than plain interleaved pixels, such as planar and chroma-subsampled `YUV` buffers,
+//! as they come out of cameras and video decoders.
+
+use super::models::{self, Family};
+use super::{channel_order, convert_vec_color_model, Channel, ColorFormat, ColorFormatConverterError};
+
+/// How the pixels of a buffer are laid out in memory.
+///
+/// - `Interleaved`: All channels of a pixel are next to each other, e.g. `RGBRGBRGB...`.
+/// - `Planar`: Every channel is stored in its own plane, one after the other, e.g. `RRR...GGG...BBB...`.
+/// - `I420`: 4:2:0 `YUV`, a full-size `Y` plane followed by quarter-size `U` and `V` planes.
+/// - `YV12`: Same as `I420`, but with the `V` plane before the `U` plane.
+/// - `NV12`: 4:2:0 `YUV`, a full-size `Y` plane followed by a single interleaved `UV` plane.
+/// - `NV21`: Same as `NV12`, but the chroma plane is interleaved as `VU`.
+/// - `YUY2`: 4:2:2 packed `YUV`, every two pixels are stored as `Y0 U Y1 V`.
+/// - `UYVY`: 4:2:2 packed `YUV`, every two pixels are stored as `U Y0 V Y1`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PixelLayout {
+    Interleaved(ColorFormat),
+    Planar(ColorFormat),
+    I420, YV12,
+    NV12, NV21,
+    YUY2, UYVY,
+}
+
+impl PixelLayout {
+    /// Returns `true` if the layout stores `YUV` data, `false` if it stores one of the [`ColorFormat`]s.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{PixelLayout, ColorFormat};
+    ///
+    /// assert!(PixelLayout::NV12.is_yuv());
+    /// assert!(!PixelLayout::Planar(ColorFormat::RGB).is_yuv());
+    /// ```
+    pub fn is_yuv(&self) -> bool {
+        return !matches!(self, PixelLayout::Interleaved(_) | PixelLayout::Planar(_));
+    }
+}
+
+/// The matrix coefficients used to go between `RGB` and `YUV`.
+/// - `BT601`: Standard definition video, and most camera and `JPEG` output.
+/// - `BT709`: High definition video.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum YuvMatrix {
+    BT601,
+    BT709,
+}
+
+impl YuvMatrix {
+    /// The `(Kr, Kb)` luma coefficients of the matrix.
+    fn coefficients(&self) -> (f32, f32) {
+        return match self {
+            YuvMatrix::BT601 => (0.299, 0.114),
+            YuvMatrix::BT709 => (0.2126, 0.0722),
+        };
+    }
+}
+
+/// The value range of the `YUV` samples.
+/// - `Limited`: "TV" range, `Y` is in `16-235` and `U`/`V` in `16-240`.
+/// - `Full`: "PC" range, all samples use the whole `0-255` range.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum YuvRange {
+    Limited,
+    Full,
+}
+
+/// The geometry of a pixel buffer: its [`PixelLayout`], its size in pixels and the row stride in bytes.
+///
+/// The `stride` is the distance in bytes between the start of two consecutive rows of the first plane,
+/// which may be larger than the row itself if the rows are padded. Planes follow each other directly,
+/// each one `stride * rows` bytes long. The chroma planes of `I420`/`YV12` use half the stride (rounded up),
+/// and the interleaved chroma plane of `NV12`/`NV21` uses the stride rounded up to an even number.
+///
+/// `matrix` and `range` are only used for the `YUV` layouts, and default to `BT601` with a `Limited` range.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{BufferLayout, PixelLayout, YuvMatrix, YuvRange};
+///
+/// let layout = BufferLayout::new(PixelLayout::I420, 4, 2);
+/// assert_eq!(layout.stride, 4);
+/// assert_eq!(layout.buffer_size(), 4 * 2 + 2 * 2); // Y plane, plus the U and V planes at 2x1 each
+///
+/// let padded = BufferLayout::new(PixelLayout::NV12, 1920, 1080)
+///     .with_stride(2048)
+///     .with_yuv(YuvMatrix::BT709, YuvRange::Limited);
+/// assert_eq!(padded.buffer_size(), 2048 * 1080 + 2048 * 540);
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct BufferLayout {
+    /// How the pixels are laid out in memory.
+    pub layout: PixelLayout,
+    /// Width of the buffer in pixels.
+    pub width: usize,
+    /// Height of the buffer in pixels.
+    pub height: usize,
+    /// Distance in bytes between the start of two rows of the first plane.
+    pub stride: usize,
+    /// `YUV` matrix coefficients, only used by the `YUV` layouts.
+    pub matrix: YuvMatrix,
+    /// `YUV` value range, only used by the `YUV` layouts.
+    pub range: YuvRange,
+}
+
+impl BufferLayout {
+    /// Creates a new `BufferLayout` with rows that aren't padded, using `BT601` limited range for `YUV` layouts.
+    pub fn new(layout: PixelLayout, width: usize, height: usize) -> Self {
+        let mut new = Self {
+            layout,
+            width,
+            height,
+            stride: 0,
+            matrix: YuvMatrix::BT601,
+            range: YuvRange::Limited,
+        };
+        new.stride = new.min_stride();
+        return new;
+    }
+
+    /// Returns the layout with the given row stride, in bytes.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        return self;
+    }
+
+    /// Returns the layout with the given `YUV` matrix and range.
+    pub fn with_yuv(mut self, matrix: YuvMatrix, range: YuvRange) -> Self {
+        self.matrix = matrix;
+        self.range = range;
+        return self;
+    }
+
+    /// The smallest valid stride for this layout, meaning the length in bytes of an unpadded row of the first plane.
+    pub fn min_stride(&self) -> usize {
+        return match self.layout {
+            PixelLayout::Interleaved(format) => self.width * format.bytes_per_pixel(),
+            PixelLayout::Planar(_) => self.width,
+            PixelLayout::I420 | PixelLayout::YV12 |
+            PixelLayout::NV12 | PixelLayout::NV21 => self.width,
+            PixelLayout::YUY2 | PixelLayout::UYVY => self.width.div_ceil(2) * 4,
+        };
+    }
+
+    /// The total size in bytes of a buffer with this layout, including padding.
+    pub fn buffer_size(&self) -> usize {
+        let chroma_rows = self.height.div_ceil(2);
+        return match self.layout {
+            PixelLayout::Interleaved(_) | PixelLayout::YUY2 | PixelLayout::UYVY => self.stride * self.height,
+            PixelLayout::Planar(format) => self.stride * self.height * format.channel_count(),
+            PixelLayout::I420 | PixelLayout::YV12 => self.stride * self.height + 2 * self.chroma_stride() * chroma_rows,
+            PixelLayout::NV12 | PixelLayout::NV21 => self.stride * self.height + self.chroma_stride() * chroma_rows,
+        };
+    }
+
+    /// The stride of the chroma planes of the 4:2:0 layouts.
+    fn chroma_stride(&self) -> usize {
+        return match self.layout {
+            PixelLayout::I420 | PixelLayout::YV12 => self.stride.div_ceil(2),
+            PixelLayout::NV12 | PixelLayout::NV21 => self.stride.div_ceil(2) * 2,
+            _ => self.stride,
+        };
+    }
+
+    fn validate(&self, data_len: usize) -> Result<(), ColorFormatConverterError> {
+        if self.width == 0 || self.height == 0 || self.stride < self.min_stride() {
+            return Err(ColorFormatConverterError::InvalidDimensions);
+        }
+        if matches!(self.layout, PixelLayout::Planar(format) if format.is_packed()) {
+            return Err(ColorFormatConverterError::UnsupportedFormat);
+        }
+        if data_len < self.buffer_size() {
+            return Err(ColorFormatConverterError::BufferTooSmall);
+        }
+        return Ok(());
+    }
+}
+
+/// Convert a buffer with the given [`BufferLayout`] into tightly packed, interleaved pixels.
+///
+/// `Interleaved` and `Planar` layouts can be converted to any format that [`convert_vec_color_model`] supports
+/// from their own format, including the format itself. The `YUV` layouts can only be converted to the
+/// `RGB` and `RGBA` formats and their permutations, and to `LinearRGB(A)`, where alpha is set to `255`.
+/// Chroma is upsampled by repeating each sample over the pixels it covers.
+///
+/// ## Parameters
+/// - `data`: input bytes, at least `layout.buffer_size()` long
+/// - `layout`: the geometry of `data`
+/// - `to`: the interleaved color model of the output
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<u8>` of `width * height * to.channel_count()` bytes without any padding,
+/// or a [`ColorFormatConverterError`] if the geometry is invalid, `data` is too small, or `to` isn't supported
+/// for the layout.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_to_interleaved, BufferLayout, PixelLayout, ColorFormat, YuvMatrix, YuvRange};
+///
+/// // Planar RGB -> interleaved RGB
+/// let planar = vec![1, 2,  3, 4,  5, 6]; // RR GG BB
+/// let layout = BufferLayout::new(PixelLayout::Planar(ColorFormat::RGB), 2, 1);
+/// let rgb = convert_to_interleaved(&planar, &layout, ColorFormat::RGB);
+/// assert_eq!(rgb, Ok(vec![1, 3, 5,  2, 4, 6]));
+///
+/// // A 2x2 white I420 image in full range -> RGBA
+/// let i420 = vec![255, 255, 255, 255,  128,  128];
+/// let layout = BufferLayout::new(PixelLayout::I420, 2, 2).with_yuv(YuvMatrix::BT601, YuvRange::Full);
+/// let rgba = convert_to_interleaved(&i420, &layout, ColorFormat::RGBA).unwrap();
+/// assert_eq!(rgba, vec![255; 16]);
+///
+/// // A mid-gray I420 image -> linear-light RGB, which is darker than the sRGB encoded value
+/// let i420 = vec![128, 128, 128, 128,  128,  128];
+/// let linear = convert_to_interleaved(&i420, &layout, ColorFormat::LinearRGB).unwrap();
+/// assert_eq!(linear, vec![55; 12]);
+/// ```
+pub fn convert_to_interleaved(data: &[u8], layout: &BufferLayout, to: ColorFormat) -> Result<Vec<u8>, ColorFormatConverterError> {
+    layout.validate(data.len())?;
+
+    let (w, h, stride) = (layout.width, layout.height, layout.stride);
+
+    let (source, from) = match layout.layout {
+        PixelLayout::Interleaved(format) => {
+            let row = w * format.bytes_per_pixel();
+            let mut out = Vec::with_capacity(row * h);
+            for y in 0..h {
+                out.extend_from_slice(&data[y * stride..y * stride + row]);
+            }
+            (out, format)
+        },
+        PixelLayout::Planar(format) => {
+            let channels = format.channel_count();
+            let plane_size = stride * h;
+            let mut out = Vec::with_capacity(w * h * channels);
+            for y in 0..h {
+                for x in 0..w {
+                    for c in 0..channels {
+                        out.push(data[c * plane_size + y * stride + x]);
+                    }
+                }
+            }
+            (out, format)
+        },
+        _ => {
+            // YUV holds sRGB encoded values, linear-light outputs are converted from them afterwards
+            let rgb = models::encoded_rgb(to).unwrap_or(to);
+            let order = rgb_order(rgb).ok_or(ColorFormatConverterError::UnsupportedFormat)?;
+            let mut out = Vec::with_capacity(w * h * order.len());
+            for y in 0..h {
+                for x in 0..w {
+                    let (yy, u, v) = read_yuv(data, layout, x, y);
+                    let (r, g, b) = yuv_to_rgb(yy, u, v, layout.matrix, layout.range);
+                    push_rgb(&mut out, order, r, g, b, 255);
+                }
+            }
+            if rgb == to { return Ok(out); }
+            return convert_vec_color_model(&out, rgb, to);
+        },
+    };
+
+    if from == to { return Ok(source); }
+    return convert_vec_color_model(&source, from, to);
+}
+
+/// Convert tightly packed, interleaved pixels into a buffer with the given [`BufferLayout`].
+///
+/// This is the reverse of [`convert_to_interleaved`]. `Interleaved` and `Planar` layouts accept any format that
+/// [`convert_vec_color_model`] can convert into their own format. The `YUV` layouts only accept the `RGB` and `RGBA`
+/// formats and their permutations, and `LinearRGB(A)`, where alpha is ignored. Chroma is downsampled by averaging the pixels each sample covers.
+/// Any padding bytes in the output are set to `0`.
+///
+/// ## Parameters
+/// - `data`: input bytes, exactly `width * height * from.channel_count()` long
+/// - `from`: the interleaved color model of `data`
+/// - `layout`: the geometry of the output
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<u8>` of `layout.buffer_size()` bytes, or a [`ColorFormatConverterError`]
+/// if the geometry is invalid, `data` doesn't match it, or `from` isn't supported for the layout.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_from_interleaved, convert_to_interleaved, BufferLayout, PixelLayout, ColorFormat};
+///
+/// // Interleaved RGB -> planar RGB
+/// let rgb = vec![1, 3, 5,  2, 4, 6];
+/// let layout = BufferLayout::new(PixelLayout::Planar(ColorFormat::RGB), 2, 1);
+/// assert_eq!(convert_from_interleaved(&rgb, ColorFormat::RGB, &layout), Ok(vec![1, 2,  3, 4,  5, 6]));
+///
+/// // Gray RGB -> YUY2 (limited range), and back
+/// let gray = vec![128; 2 * 3];
+/// let layout = BufferLayout::new(PixelLayout::YUY2, 2, 1);
+/// let yuy2 = convert_from_interleaved(&gray, ColorFormat::RGB, &layout).unwrap();
+/// assert_eq!(yuy2, vec![126, 128, 126, 128]);
+/// assert_eq!(convert_to_interleaved(&yuy2, &layout, ColorFormat::RGB), Ok(gray));
+///
+/// // Linear-light RGB is encoded to sRGB before it's converted to YUV
+/// let linear = vec![55; 2 * 3];
+/// assert_eq!(convert_from_interleaved(&linear, ColorFormat::LinearRGB, &layout), Ok(yuy2));
+/// ```
+pub fn convert_from_interleaved(data: &[u8], from: ColorFormat, layout: &BufferLayout) -> Result<Vec<u8>, ColorFormatConverterError> {
+    let (w, h, stride) = (layout.width, layout.height, layout.stride);
+    layout.validate(layout.buffer_size())?;
+    if data.len() != w * h * from.bytes_per_pixel() {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+
+    let mut out = vec![0u8; layout.buffer_size()];
+
+    match layout.layout {
+        PixelLayout::Interleaved(format) | PixelLayout::Planar(format) => {
+            let converted;
+            let pixels = if from == format { data } else {
+                converted = convert_vec_color_model(data, from, format)?;
+                &converted
+            };
+            let channels = format.bytes_per_pixel();
+
+            if let PixelLayout::Interleaved(_) = layout.layout {
+                let row = w * channels;
+                for y in 0..h {
+                    out[y * stride..y * stride + row].copy_from_slice(&pixels[y * row..(y + 1) * row]);
+                }
+            } else {
+                let plane_size = stride * h;
+                for (i, pixel) in pixels.chunks_exact(channels).enumerate() {
+                    let (x, y) = (i % w, i / w);
+                    for (c, &value) in pixel.iter().enumerate() {
+                        out[c * plane_size + y * stride + x] = value;
+                    }
+                }
+            }
+        },
+        _ => {
+            // linear-light inputs are encoded to sRGB first, as YUV holds sRGB encoded values
+            let encoded;
+            let (data, from) = match models::encoded_rgb(from) {
+                Some(rgb) => {
+                    encoded = convert_vec_color_model(data, from, rgb)?;
+                    (encoded.as_slice(), rgb)
+                },
+                None => (data, from),
+            };
+            let order = rgb_order(from).ok_or(ColorFormatConverterError::UnsupportedFormat)?;
+            let channels = order.len();
+            let pixel_rgb = |x: usize, y: usize| -> (f32, f32, f32) {
+                let base = (y * w + x) * channels;
+                let mut rgb = (0.0, 0.0, 0.0);
+                for (i, ch) in order.iter().enumerate() {
+                    let value = data[base + i] as f32 / 255.0;
+                    match ch {
+                        Channel::R => rgb.0 = value,
+                        Channel::G => rgb.1 = value,
+                        Channel::B => rgb.2 = value,
+                        _ => {},
+                    }
+                }
+                rgb
+            };
+
+            let (kr, kb) = layout.matrix.coefficients();
+            let (sub_x, sub_y) = match layout.layout {
+                PixelLayout::YUY2 | PixelLayout::UYVY => (2, 1),
+                _ => (2, 2),
+            };
+
+            // luma, one sample per pixel
+            for y in 0..h {
+                for x in 0..w {
+                    let (r, g, b) = pixel_rgb(x, y);
+                    let luma = kr * r + (1.0 - kr - kb) * g + kb * b;
+                    let index = match layout.layout {
+                        PixelLayout::YUY2 => y * stride + x * 2,
+                        PixelLayout::UYVY => y * stride + x * 2 + 1,
+                        _ => y * stride + x,
+                    };
+                    out[index] = encode_luma(luma, layout.range);
+                }
+            }
+
+            // chroma, one sample per block, averaged over the pixels inside the image
+            for cy in 0..h.div_ceil(sub_y) {
+                for cx in 0..w.div_ceil(sub_x) {
+                    let (mut u_sum, mut v_sum, mut count) = (0.0, 0.0, 0.0);
+                    for y in cy * sub_y..((cy + 1) * sub_y).min(h) {
+                        for x in cx * sub_x..((cx + 1) * sub_x).min(w) {
+                            let (r, g, b) = pixel_rgb(x, y);
+                            let luma = kr * r + (1.0 - kr - kb) * g + kb * b;
+                            u_sum += (b - luma) / (2.0 * (1.0 - kb));
+                            v_sum += (r - luma) / (2.0 * (1.0 - kr));
+                            count += 1.0;
+                        }
+                    }
+                    let u = encode_chroma(u_sum / count, layout.range);
+                    let v = encode_chroma(v_sum / count, layout.range);
+
+                    let (u_index, v_index) = chroma_indices(layout, cx, cy);
+                    out[u_index] = u;
+                    out[v_index] = v;
+                }
+            }
+        },
+    }
+
+    return Ok(out);
+}
+
+/// The channel order of an `sRGB` encoded `RGB` or `RGBA` format permutation, or `None` for any other format.
+fn rgb_order(format: ColorFormat) -> Option<&'static [Channel]> {
+    return if models::family(format) == Family::Rgb { Some(channel_order(format)) } else { None };
+}
+
+fn push_rgb(out: &mut Vec<u8>, order: &[Channel], r: u8, g: u8, b: u8, a: u8) {
+    for ch in order {
+        out.push(match ch {
+            Channel::R => r,
+            Channel::G => g,
+            Channel::B => b,
+            _ => a,
+        });
+    }
+}
+
+/// The byte indices of the `U` and `V` samples of the chroma block at `(cx, cy)`.
+fn chroma_indices(layout: &BufferLayout, cx: usize, cy: usize) -> (usize, usize) {
+    let luma_size = layout.stride * layout.height;
+    let chroma_stride = layout.chroma_stride();
+    let chroma_plane = chroma_stride * layout.height.div_ceil(2);
+
+    return match layout.layout {
+        PixelLayout::I420 => {
+            let offset = cy * chroma_stride + cx;
+            (luma_size + offset, luma_size + chroma_plane + offset)
+        },
+        PixelLayout::YV12 => {
+            let offset = cy * chroma_stride + cx;
+            (luma_size + chroma_plane + offset, luma_size + offset)
+        },
+        PixelLayout::NV12 => {
+            let offset = luma_size + cy * chroma_stride + cx * 2;
+            (offset, offset + 1)
+        },
+        PixelLayout::NV21 => {
+            let offset = luma_size + cy * chroma_stride + cx * 2;
+            (offset + 1, offset)
+        },
+        PixelLayout::YUY2 => {
+            let offset = cy * layout.stride + cx * 4;
+            (offset + 1, offset + 3)
+        },
+        PixelLayout::UYVY => {
+            let offset = cy * layout.stride + cx * 4;
+            (offset, offset + 2)
+        },
+        PixelLayout::Interleaved(_) | PixelLayout::Planar(_) => unreachable!("Only called for YUV layouts."),
+    };
+}
+
+/// Reads the `Y`, `U` and `V` samples that belong to the pixel at `(x, y)`.
+fn read_yuv(data: &[u8], layout: &BufferLayout, x: usize, y: usize) -> (u8, u8, u8) {
+    let luma = match layout.layout {
+        PixelLayout::YUY2 => data[y * layout.stride + x * 2],
+        PixelLayout::UYVY => data[y * layout.stride + x * 2 + 1],
+        _ => data[y * layout.stride + x],
+    };
+    let (u_index, v_index) = match layout.layout {
+        PixelLayout::YUY2 | PixelLayout::UYVY => chroma_indices(layout, x / 2, y),
+        _ => chroma_indices(layout, x / 2, y / 2),
+    };
+    return (luma, data[u_index], data[v_index]);
+}
+
+fn encode_luma(luma: f32, range: YuvRange) -> u8 {
+    let value = match range {
+        YuvRange::Limited => 16.0 + 219.0 * luma,
+        YuvRange::Full => 255.0 * luma,
+    };
+    return value.round().clamp(0.0, 255.0) as u8;
+}
+
+fn encode_chroma(chroma: f32, range: YuvRange) -> u8 {
+    let value = match range {
+        YuvRange::Limited => 128.0 + 224.0 * chroma,
+        YuvRange::Full => 128.0 + 255.0 * chroma,
+    };
+    return value.round().clamp(0.0, 255.0) as u8;
+}
+
+/// Converts a single `YUV` sample triplet to `RGB`, clamping the result to `0-255`.
+fn yuv_to_rgb(y: u8, u: u8, v: u8, matrix: YuvMatrix, range: YuvRange) -> (u8, u8, u8) {
+    let (kr, kb) = matrix.coefficients();
+    let kg = 1.0 - kr - kb;
+
+    let (luma, u, v) = match range {
+        YuvRange::Limited => ((y as f32 - 16.0) / 219.0, (u as f32 - 128.0) / 224.0, (v as f32 - 128.0) / 224.0),
+        YuvRange::Full => (y as f32 / 255.0, (u as f32 - 128.0) / 255.0, (v as f32 - 128.0) / 255.0),
+    };
+
+    let r = luma + 2.0 * (1.0 - kr) * v;
+    let b = luma + 2.0 * (1.0 - kb) * u;
+    let g = (luma - kr * r - kb * b) / kg;
+
+    let to_byte = |c: f32| (c * 255.0).round().clamp(0.0, 255.0) as u8;
+    return (to_byte(r), to_byte(g), to_byte(b));
+}
@@ -0,0 +1,317 @@
+//! Color lookup tables (`LUT`s) in the Adobe / DaVinci Resolve `.cube` format, for color grading.
+//!
+//! A `1D` LUT maps every channel on its own through a curve, a `3D` LUT maps whole colors through a lattice,
+//! which can also change hue and saturation.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::models::map_colors;
+use super::{ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// The sizes the `.cube` format allows, for `1D` and `3D` LUTs.
+const SIZES_1D: std::ops::RangeInclusive<usize> = 2..=65536;
+const SIZES_3D: std::ops::RangeInclusive<usize> = 2..=256;
+
+/// The kind of a [`CubeLut`].
+///
+/// - `OneD`: Every channel is mapped through its own curve, of `size` entries.
+/// - `ThreeD`: Colors are mapped through a lattice of `size³` entries.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LutDimension {
+    OneD,
+    ThreeD,
+}
+
+/// How colors between the entries of a `3D` LUT are interpolated. `1D` LUTs are always interpolated linearly.
+///
+/// - `Trilinear`: Blends the 8 entries around the color. Simple, but can slightly desaturate neutral colors.
+/// - `Tetrahedral`: Blends the 4 entries of the tetrahedron around the color, which keeps grays exactly on the gray axis.
+///   This is what most grading software uses.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LutInterpolation {
+    Trilinear,
+    Tetrahedral,
+}
+
+/// A color lookup table, as stored in a `.cube` file.
+///
+/// LUTs are parsed with [`str::parse`], written with [`CubeLut::to_cube`], and applied to buffers with [`apply_lut`].
+/// Both the Adobe keywords (`DOMAIN_MIN`, `DOMAIN_MAX`) and the Resolve ones (`LUT_1D_INPUT_RANGE`, `LUT_3D_INPUT_RANGE`) are read.
+/// Files that hold both a `1D` and a `3D` LUT aren't supported.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{CubeLut, LutDimension, LutInterpolation};
+///
+/// // A 1D LUT that inverts every channel
+/// let text = "TITLE \"Invert\"\nLUT_1D_SIZE 2\n1 1 1\n0 0 0\n";
+/// let lut: CubeLut = text.parse().unwrap();
+/// assert_eq!(lut.title.as_deref(), Some("Invert"));
+/// assert_eq!(lut.dimension, LutDimension::OneD);
+/// assert_eq!(lut.lookup([0.25, 0.5, 1.0], LutInterpolation::Tetrahedral), [0.75, 0.5, 0.0]);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct CubeLut {
+    /// The title of the LUT, from the `TITLE` line.
+    pub title: Option<String>,
+    /// Whether the LUT is `1D` or `3D`.
+    pub dimension: LutDimension,
+    /// The amount of entries per axis.
+    pub size: usize,
+    /// The input values mapped to the first entry, per channel. Usually `0.0`.
+    pub domain_min: [f32; 3],
+    /// The input values mapped to the last entry, per channel. Usually `1.0`.
+    pub domain_max: [f32; 3],
+    /// The output colors: `size` entries for a `1D` LUT, or `size³` for a `3D` LUT,
+    /// with red changing fastest, then green, then blue.
+    pub table: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    /// Create a LUT that maps every color to itself, e.g. as a starting point for grading in other software.
+    ///
+    /// ## Parameters
+    /// - `dimension`: whether to create a `1D` or `3D` LUT
+    /// - `size`: the amount of entries per axis, `2-65536` for `1D`, and `2-256` for `3D` (usually `17`, `33` or `65`)
+    ///
+    /// ## Returns
+    /// A `Result` containing either the LUT, or `OutOfRange` for an invalid size.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{CubeLut, LutDimension};
+    ///
+    /// let lut = CubeLut::identity(LutDimension::ThreeD, 2).unwrap();
+    /// assert_eq!(lut.table.len(), 8);
+    /// assert!(lut.to_cube().starts_with("LUT_3D_SIZE 2\n0.000000 0.000000 0.000000\n1.000000 0.000000 0.000000\n"));
+    /// ```
+    pub fn identity(dimension: LutDimension, size: usize) -> Result<Self, ColorFormatConverterError> {
+        if !valid_size(dimension, size) {
+            return Err(ColorFormatConverterError::OutOfRange);
+        }
+        let level = |i: usize| i as f32 / (size - 1) as f32;
+        let table = match dimension {
+            LutDimension::OneD => (0..size).map(|i| [level(i); 3]).collect(),
+            LutDimension::ThreeD => (0..size * size * size)
+                .map(|i| [level(i % size), level(i / size % size), level(i / (size * size))])
+                .collect(),
+        };
+        return Ok(Self { title: None, dimension, size, domain_min: [0.0; 3], domain_max: [1.0; 3], table });
+    }
+
+    /// Write the LUT in the `.cube` format, with 6 decimals per value. The domain is only written if it isn't `0.0-1.0`.
+    pub fn to_cube(&self) -> String {
+        let mut text = String::new();
+        if let Some(title) = &self.title {
+            let _ = writeln!(text, "TITLE \"{}\"", title);
+        }
+        let keyword = match self.dimension {
+            LutDimension::OneD => "LUT_1D_SIZE",
+            LutDimension::ThreeD => "LUT_3D_SIZE",
+        };
+        let _ = writeln!(text, "{} {}", keyword, self.size);
+        if self.domain_min != [0.0; 3] || self.domain_max != [1.0; 3] {
+            let [r, g, b] = self.domain_min;
+            let _ = writeln!(text, "DOMAIN_MIN {:.6} {:.6} {:.6}", r, g, b);
+            let [r, g, b] = self.domain_max;
+            let _ = writeln!(text, "DOMAIN_MAX {:.6} {:.6} {:.6}", r, g, b);
+        }
+        for [r, g, b] in &self.table {
+            let _ = writeln!(text, "{:.6} {:.6} {:.6}", r, g, b);
+        }
+        return text;
+    }
+
+    /// Map a single color through the LUT. Inputs outside of the domain are clamped to it.
+    ///
+    /// ## Parameters
+    /// - `rgb`: the input color
+    /// - `interpolation`: how `3D` LUTs are interpolated, ignored for `1D` LUTs
+    ///
+    /// ## Returns
+    /// The output color, as stored in the LUT, so it may be outside of `0.0-1.0`.
+    ///
+    /// ## Panics
+    /// If `size` is smaller than `2`, or `table` doesn't have the amount of entries `size` needs.
+    pub fn lookup(&self, rgb: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        let position: [f32; 3] = std::array::from_fn(|i| {
+            let range = self.domain_max[i] - self.domain_min[i];
+            let t = if range > 0.0 { (rgb[i] - self.domain_min[i]) / range } else { 0.0 };
+            let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+            t * (self.size - 1) as f32
+        });
+        return match (self.dimension, interpolation) {
+            (LutDimension::OneD, _) => std::array::from_fn(|ch| {
+                let (i, f) = split(position[ch], self.size);
+                self.table[i][ch] + (self.table[i + 1][ch] - self.table[i][ch]) * f
+            }),
+            (LutDimension::ThreeD, LutInterpolation::Trilinear) => self.trilinear(position),
+            (LutDimension::ThreeD, LutInterpolation::Tetrahedral) => self.tetrahedral(position),
+        };
+    }
+
+    /// The entry at the lattice point `(r, g, b)`.
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        return self.table[r + self.size * (g + self.size * b)];
+    }
+
+    fn trilinear(&self, position: [f32; 3]) -> [f32; 3] {
+        let [(r, fr), (g, fg), (b, fb)] = position.map(|p| split(p, self.size));
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| -> [f32; 3] { std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t) };
+
+        let c00 = lerp(self.entry(r, g, b), self.entry(r + 1, g, b), fr);
+        let c10 = lerp(self.entry(r, g + 1, b), self.entry(r + 1, g + 1, b), fr);
+        let c01 = lerp(self.entry(r, g, b + 1), self.entry(r + 1, g, b + 1), fr);
+        let c11 = lerp(self.entry(r, g + 1, b + 1), self.entry(r + 1, g + 1, b + 1), fr);
+        return lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb);
+    }
+
+    fn tetrahedral(&self, position: [f32; 3]) -> [f32; 3] {
+        let [(r, fr), (g, fg), (b, fb)] = position.map(|p| split(p, self.size));
+        let corner = |dr: usize, dg: usize, db: usize| self.entry(r + dr, g + dg, b + db);
+
+        let c000 = corner(0, 0, 0);
+        let c111 = corner(1, 1, 1);
+        // the two corners between c000 and c111, and the weights of all four, depend on the order of the fractions
+        let (c1, c2, weights) = if fr > fg {
+            if fg > fb {
+                (corner(1, 0, 0), corner(1, 1, 0), [1.0 - fr, fr - fg, fg - fb, fb])
+            } else if fr > fb {
+                (corner(1, 0, 0), corner(1, 0, 1), [1.0 - fr, fr - fb, fb - fg, fg])
+            } else {
+                (corner(0, 0, 1), corner(1, 0, 1), [1.0 - fb, fb - fr, fr - fg, fg])
+            }
+        } else if fb > fg {
+            (corner(0, 0, 1), corner(0, 1, 1), [1.0 - fb, fb - fg, fg - fr, fr])
+        } else if fb > fr {
+            (corner(0, 1, 0), corner(0, 1, 1), [1.0 - fg, fg - fb, fb - fr, fr])
+        } else {
+            (corner(0, 1, 0), corner(1, 1, 0), [1.0 - fg, fg - fr, fr - fb, fb])
+        };
+        let [w0, w1, w2, w3] = weights;
+        return std::array::from_fn(|i| c000[i] * w0 + c1[i] * w1 + c2[i] * w2 + c111[i] * w3);
+    }
+}
+
+impl FromStr for CubeLut {
+    type Err = ColorFormatConverterError;
+
+    /// Parses the text of a `.cube` file. Returns `InvalidLut` for malformed lines, a missing or invalid size,
+    /// an empty domain, or a wrong amount of entries. Unknown keywords are skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut title = None;
+        let mut sizes = (None, None);
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match keyword {
+                "TITLE" => title = Some(rest.trim_matches('"').to_string()),
+                "LUT_1D_SIZE" => sizes.0 = Some(rest.parse::<usize>().map_err(|_| ColorFormatConverterError::InvalidLut)?),
+                "LUT_3D_SIZE" => sizes.1 = Some(rest.parse::<usize>().map_err(|_| ColorFormatConverterError::InvalidLut)?),
+                "DOMAIN_MIN" => domain_min = parse_triplet(rest)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(rest)?,
+                "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                    let range = parse_values(rest)?;
+                    let [min, max] = range[..] else { return Err(ColorFormatConverterError::InvalidLut) };
+                    domain_min = [min; 3];
+                    domain_max = [max; 3];
+                },
+                _ if keyword.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) => {
+                    table.push(parse_triplet(line)?);
+                },
+                _ => {},
+            }
+        }
+
+        let (dimension, size) = match sizes {
+            (Some(size), None) => (LutDimension::OneD, size),
+            (None, Some(size)) => (LutDimension::ThreeD, size),
+            _ => return Err(ColorFormatConverterError::InvalidLut),
+        };
+        if !valid_size(dimension, size) || table.len() != entries(dimension, size) || (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(ColorFormatConverterError::InvalidLut);
+        }
+        return Ok(Self { title, dimension, size, domain_min, domain_max, table });
+    }
+}
+
+/// Apply a LUT to every pixel of a buffer, e.g. for color grading. See [`CubeLut`].
+///
+/// The LUT is applied to the `sRGB` values of the pixels, and its output clamped to `0.0-1.0`. Alpha is kept.
+///
+/// ## Parameters
+/// - `data`: input slice of pixels, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`
+/// - `lut`: the LUT to apply
+/// - `interpolation`: how `3D` LUTs are interpolated
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format, or a [`ColorFormatConverterError`]:
+/// `InvalidLut` if `table` doesn't have the amount of entries `size` needs, `InvalidInputLength` if `data` has a wrong length,
+/// or `NotANumber` or `OutOfRange` for invalid float values.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{apply_lut, ColorFormat, CubeLut, LutInterpolation};
+///
+/// // A 3D LUT that swaps red and blue
+/// let lut: CubeLut = "LUT_3D_SIZE 2\n0 0 0\n0 0 1\n0 1 0\n0 1 1\n1 0 0\n1 0 1\n1 1 0\n1 1 1\n".parse().unwrap();
+/// let rgba: Vec<u8> = vec![255, 128, 0, 255];
+/// let graded = apply_lut(&rgba, ColorFormat::RGBA, &lut, LutInterpolation::Tetrahedral).unwrap();
+/// assert_eq!(graded, vec![0, 128, 255, 255]);
+/// ```
+pub fn apply_lut<T: ChannelType>(
+    data: &[T],
+    format: ColorFormat,
+    lut: &CubeLut,
+    interpolation: LutInterpolation
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    if lut.size < 2 || lut.table.len() != entries(lut.dimension, lut.size) {
+        return Err(ColorFormatConverterError::InvalidLut);
+    }
+    return map_colors(data, format, |[r, g, b, a]| {
+        let [r, g, b] = lut.lookup([r, g, b], interpolation).map(|v| v.clamp(0.0, 1.0));
+        [r, g, b, a]
+    });
+}
+
+fn valid_size(dimension: LutDimension, size: usize) -> bool {
+    return match dimension {
+        LutDimension::OneD => SIZES_1D.contains(&size),
+        LutDimension::ThreeD => SIZES_3D.contains(&size),
+    };
+}
+
+/// The amount of entries of a LUT of `size` entries per axis.
+fn entries(dimension: LutDimension, size: usize) -> usize {
+    return match dimension {
+        LutDimension::OneD => size,
+        LutDimension::ThreeD => size.saturating_pow(3),
+    };
+}
+
+/// Splits a lattice position into the index of the entry below it and the fraction towards the next, staying inside the lattice.
+fn split(position: f32, size: usize) -> (usize, f32) {
+    let i = (position as usize).min(size - 2);
+    return (i, position - i as f32);
+}
+
+fn parse_values(text: &str) -> Result<Vec<f32>, ColorFormatConverterError> {
+    return text.split_whitespace()
+        .map(|v| v.parse::<f32>().ok().filter(|v| v.is_finite()).ok_or(ColorFormatConverterError::InvalidLut))
+        .collect();
+}
+
+fn parse_triplet(text: &str) -> Result<[f32; 3], ColorFormatConverterError> {
+    return parse_values(text)?.try_into().map_err(|_| ColorFormatConverterError::InvalidLut);
+}
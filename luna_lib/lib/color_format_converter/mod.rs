@@ -3,13 +3,126 @@
 //! The conversion is done by mapping the channels of the source color model to the destination color model.
 //! Currently, only models with RGB, CMYK, HSL and Grayscale are supported, with and without an Alpha channel,
 //! with all their respective permutations.
-
-pub const VERSION: crate::Version = crate::Version::new(2, 0, 0);
+//! 
+//! Besides interleaved pixels, planar and chroma-subsampled `YUV` buffers (`I420`, `NV12`, `YUY2`, ...) can be
+//! converted to and from interleaved `RGB(A)` through a [`BufferLayout`].
+//! 
+//! Channels are `u8` by default, but `u16` and `f32` channels are supported as well, see [`ChannelType`].
+//! The packed formats `RGB565`, `RGBA4444`, `RGBA5551` and `RGB10A2` can be converted to and from the `RGB` formats.
+//! 
+//! Formats of different families (e.g. `RGB` and `CMYK`) are converted through `sRGB`, and `LinearRGB(A)` holds
+//! linear-light values, for math that should be done on light rather than on encoded values. See [`TransferFunction`].
+//! 
+//! The device-independent spaces `CIE XYZ`, `CIE L*a*b*`, `LCh(ab)`, `OKLab` and `OKLCH` are part of the same
+//! conversion path, with `f32` channels.
+//! 
+//! Two colors in any format can be compared with the color difference metrics, such as [`delta_e_2000`],
+//! and checked for readable contrast with [`contrast_ratio`], [`meets_wcag`] and [`apca_contrast`].
+//! Whole buffers can be checked for accessibility with [`simulate_color_vision_deficiency`], and adjusted to a
+//! different light with [`white_balance`].
+//! 
+//! Single colors can be parsed from and printed as `CSS` color strings with [`Color`], and palettes generated
+//! from them with [`harmony`], [`tints`], [`shades`], [`tones`] and [`ramp`], or blended into a [`Gradient`].
+//! 
+//! Buffers can be reduced to a palette and an indexed buffer with [`quantize`], e.g. for `GIF` export,
+//! and their most common colors found with [`dominant_colors`].
+//! 
+//! Colors can be printed in a terminal with the `SGR` escape sequences of [`sgr_foreground`] and [`sgr_background`],
+//! mapped to the xterm palettes with [`nearest_ansi16`] and [`nearest_ansi256`], and whole buffers previewed with [`render_terminal`].
+//! 
+//! Buffers with alpha can be converted to and from premultiplied alpha with [`premultiply`] and [`unpremultiply`],
+//! and layered with the Porter-Duff operators of [`composite`] or the blend modes of [`blend`].
+//! 
+//! For print, colors can be separated into `CMYK` with a chosen black generation, `UCR` or `GCR`, and a total ink limit,
+//! see [`SeparationOptions`].
+//! 
+//! Besides `sRGB`, the wide-gamut `RGB` spaces `Display P3`, `Rec.2020` and `Adobe RGB` are supported through [`RgbSpace`],
+//! and colors out of a space's gamut can be detected with [`is_in_gamut`] and brought into it with [`gamut_map`].
+//! 
+//! Large buffers, e.g. whole video frames, can be converted on several threads with [`convert_vec_color_model_parallel`],
+//! and without allocating into an existing buffer with [`convert_into`], or in place with [`convert_in_place`].
+//! Single channels can be taken out with [`extract_channel`] and put back with [`replace_channel`], and channels reordered,
+//! copied or filled freely with a [`swizzle`] pattern like `"BGR1"`.
+//! 
+//! Color grading LUTs in the `.cube` format can be read, written and applied with [`CubeLut`] and [`apply_lut`].
+//! Palettes can be moved between tools with [`read_palette`] and [`write_palette`], in the GIMP, Adobe, Paint.NET and hex list formats.
+//! 
+//! Measured spectra, of light sources or of surface reflectances, can be converted to `XYZ` and any other model with [`Spectrum`],
+//! using the `CIE 1931` and `CIE 1964` standard [`Observer`]s and the standard [`Illuminant`]s.
+//! 
+//! Raw camera sensor data in a Bayer mosaic (`RGGB`, `BGGR`, `GRBG` or `GBRG`) can be turned into `RGB` pixels with [`demosaic`].
+
+mod color;
+mod compositing;
+mod contrast;
+mod css;
+mod demosaic;
+mod depth;
+mod difference;
+mod gamut;
+mod gradient;
+mod layout;
+mod lut;
+mod models;
+mod packed;
+mod palette;
+mod parallel;
+mod quantize;
+mod separation;
+mod spaces;
+mod spectral;
+mod swatches;
+mod swizzle;
+mod temperature;
+mod terminal;
+mod transfer;
+mod vision;
+pub use color::Color;
+pub use compositing::*;
+pub use contrast::*;
+pub use demosaic::*;
+pub use depth::*;
+pub use difference::{delta_e_76, delta_e_94, delta_e_2000, delta_e_ok};
+pub use gamut::*;
+pub use gradient::*;
+pub use layout::*;
+pub use lut::*;
+pub use packed::convert_vec_color_model_dithered;
+pub use palette::{harmony, rotate_hue, tints, shades, tones, ramp, lightness_ramp, Harmony, HueSpace};
+pub use parallel::*;
+pub use quantize::*;
+pub use separation::*;
+pub use spaces::{
+    WhitePoint,
+    from_rgb_to_xyz, from_xyz_to_rgb,
+    from_rgb_to_lab, from_lab_to_rgb,
+    from_rgb_to_lch, from_lch_to_rgb,
+    from_rgb_to_oklab, from_oklab_to_rgb,
+    from_rgb_to_oklch, from_oklch_to_rgb,
+};
+pub use spectral::*;
+pub use swatches::*;
+pub use swizzle::{extract_channel, replace_channel, swizzle};
+pub use temperature::*;
+pub use terminal::*;
+pub use transfer::*;
+pub use vision::*;
+
+pub const VERSION: crate::Version = crate::Version::new(2, 25, 0);
 
 /// Possible errors that can occur during color format conversion, throughout this module.
 /// - `InvalidInputLength`: The input data length is not a multiple of the source format's channel count.
 /// - `SameFormat`: The source and destination formats are the same.
 /// - `OutOfRange`: The input channel value is out of range for the given color format.
+/// - `InvalidDimensions`: The width, height or stride of a buffer are zero or don't fit together.
+/// - `BufferTooSmall`: The input buffer is smaller than the given geometry requires.
+/// - `UnsupportedFormat`: The requested conversion isn't possible for the given color formats.
+/// - `NotANumber`: A floating-point input channel value is `NaN`.
+/// - `InvalidColorString`: A string isn't a valid or supported `CSS` color.
+/// - `InvalidOutputLength`: The output buffer doesn't have the length the conversion produces.
+/// - `InvalidSwizzle`: A swizzle pattern is empty, too long, or names a channel the format doesn't have.
+/// - `InvalidLut`: A `.cube` LUT can't be parsed, or doesn't have as many entries as its size needs.
+/// - `InvalidPalette`: A palette file can't be parsed, or swatches don't fit a palette format.
 /// 
 /// # Examples
 /// ```rust
@@ -37,6 +150,24 @@ pub enum ColorFormatConverterError {
     SameFormat,
     /// Error signifying that the input channel value is out of range for the given color format.
     OutOfRange,
+    /// Error signifying that the width, height or stride of a buffer are zero, or that the stride is smaller than a row.
+    InvalidDimensions,
+    /// Error signifying that the input buffer is smaller than the given geometry requires.
+    BufferTooSmall,
+    /// Error signifying that the requested conversion isn't possible for the given color formats.
+    UnsupportedFormat,
+    /// Error signifying that a floating-point input channel value is `NaN`.
+    NotANumber,
+    /// Error signifying that a string isn't a valid or supported `CSS` color.
+    InvalidColorString,
+    /// Error signifying that the output buffer doesn't have the length the conversion produces.
+    InvalidOutputLength,
+    /// Error signifying that a swizzle pattern is empty, too long, or names a channel the format doesn't have.
+    InvalidSwizzle,
+    /// Error signifying that a `.cube` LUT can't be parsed, or doesn't have as many entries as its size needs.
+    InvalidLut,
+    /// Error signifying that a palette file can't be parsed, or that swatches don't fit a palette format.
+    InvalidPalette,
 }
 
 impl std::fmt::Display for ColorFormatConverterError {
@@ -45,6 +176,15 @@ impl std::fmt::Display for ColorFormatConverterError {
             ColorFormatConverterError::InvalidInputLength => write!(f, "Input data length is not a multiple of the source format's channel count."),
             ColorFormatConverterError::SameFormat => write!(f, "Source and destination formats are the same."),
             ColorFormatConverterError::OutOfRange => write!(f, "Input channel value is out of range for the given color format."),
+            ColorFormatConverterError::InvalidDimensions => write!(f, "Buffer width, height or stride are invalid."),
+            ColorFormatConverterError::BufferTooSmall => write!(f, "Input buffer is too small for the given geometry."),
+            ColorFormatConverterError::UnsupportedFormat => write!(f, "Conversion is not supported for the given color formats."),
+            ColorFormatConverterError::NotANumber => write!(f, "Input channel value is NaN."),
+            ColorFormatConverterError::InvalidColorString => write!(f, "String is not a valid CSS color."),
+            ColorFormatConverterError::InvalidOutputLength => write!(f, "Output buffer length doesn't match the converted data."),
+            ColorFormatConverterError::InvalidSwizzle => write!(f, "Swizzle pattern is invalid for the given color format."),
+            ColorFormatConverterError::InvalidLut => write!(f, "LUT is malformed or has the wrong amount of entries."),
+            ColorFormatConverterError::InvalidPalette => write!(f, "Palette file is malformed, or can't hold the swatches."),
         }
     }
 }
@@ -52,6 +192,23 @@ impl std::fmt::Display for ColorFormatConverterError {
 
 // TODO maybe add lume and other formats? needs calculations etc but its fine i think
 /// Packed-byte color models.
+/// 
+/// All formats store one byte (or one [`ChannelType`] value) per channel, except for the packed formats,
+/// which store a whole pixel in a little-endian word:
+/// - `RGB565`: 16 bits, `R` in the 5 highest bits, then 6 bits of `G` and 5 bits of `B`.
+/// - `RGBA4444`: 16 bits, 4 bits per channel, `R` in the highest bits and `A` in the lowest.
+/// - `RGBA5551`: 16 bits, 5 bits each for `R`, `G` and `B` from the highest bits down, and 1 bit of `A` in the lowest bit.
+/// - `RGB10A2`: 32 bits, 10 bits each for `R`, `G` and `B` from the lowest bits up, and 2 bits of `A` in the highest bits.
+/// 
+/// All `RGB` formats are `sRGB` encoded, except for `LinearRGB` and `LinearRGBA`, which hold linear light.
+/// `HSL` formats store the hue normalized, so `0-360` degrees map to the full range of the channel.
+/// 
+/// The device-independent formats hold their values as they are, so they can only be used with `f32` channels:
+/// - `XYZ`, `XYZD50`: `CIE XYZ` relative to a `D65` or `D50` white point, with `Y` in `0-1`.
+/// - `Lab`: `CIE L*a*b*` relative to `D50`, with `L*` in `0-100`.
+/// - `LCh`: `CIE LCh(ab)` relative to `D50`, with the hue in degrees.
+/// - `OKLab`: `OKLab`, with `L` in `0-1`.
+/// - `OKLCh`: `OKLCH`, with the hue in degrees.
 #[repr(usize)]
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ColorFormat {
@@ -75,6 +232,14 @@ pub enum ColorFormat {
 
     HSL, HLS, SHL, SLH, LHS, LSH,
     HSLA, HLSA, SHLA, SLHA, LHSA, LSHA,
+
+    RGB565, RGBA4444, RGBA5551, RGB10A2,
+
+    LinearRGB, LinearRGBA,
+
+    XYZ, XYZA, XYZD50, XYZD50A,
+    Lab, LabA, LCh, LChA,
+    OKLab, OKLabA, OKLCh, OKLChA,
 }
 
 impl ColorFormat {
@@ -93,16 +258,93 @@ impl ColorFormat {
     pub fn channel_count(&self) -> usize {
         return channel_order(*self).len();
     }
+
+    /// Returns the number of bytes a single pixel takes up, with `u8` channels.
+    /// This is the same as the channel count, except for the packed formats.
+    /// 
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::ColorFormat;
+    /// 
+    /// assert_eq!(ColorFormat::RGBA.bytes_per_pixel(), 4);
+    /// assert_eq!(ColorFormat::RGB565.bytes_per_pixel(), 2);
+    /// assert_eq!(ColorFormat::RGB565.channel_count(), 3);
+    /// assert_eq!(ColorFormat::RGB10A2.bytes_per_pixel(), 4);
+    /// ```
+    pub fn bytes_per_pixel(&self) -> usize {
+        return packed::packed_bytes(*self).unwrap_or(self.channel_count());
+    }
+
+    /// Returns `true` if the format is one of the packed formats, which store a whole pixel in a single word.
+    pub fn is_packed(&self) -> bool {
+        return packed::is_packed(*self);
+    }
+
+    /// Returns the channels of a pixel, in order. Packed formats return the channels they hold.
+    pub fn channels(&self) -> &'static [Channel] {
+        return channel_order(*self);
+    }
 }
 
 /// A color channel identifier.
-/// Used to specify the order of channels in a color model.
+/// Used to specify the order of channels in a color model, see [`ColorFormat::channels()`], and to pick channels with
+/// [`extract_channel`], [`replace_channel`] and [`swizzle`].
+///
+/// Every channel has a letter, which is unique within each format:
+/// - `R`, `G`, `B`: Red, green and blue, of the `RGB` and `LinearRGB` formats (`R`, `G`, `B`).
+/// - `A`: Alpha, of every format that has one (`A`).
+/// - `Gray`: The gray level of `Gray` and `GrayA` (`Y`).
+/// - `C`, `M`, `Y`, `K`: Cyan, magenta, yellow and black (`C`, `M`, `Y`, `K`).
+/// - `H`, `S`, `L`: Hue, saturation and lightness of `HSL` (`H`, `S`, `L`).
+/// - `CieX`, `CieY`, `CieZ`: The tristimulus values of `CIE XYZ` (`X`, `Y`, `Z`).
+/// - `LabL`, `LabA`, `LabB`: Lightness and the two opponent axes of `Lab` and `OKLab` (`L`, `a`, `b`).
+///   `LabL` is the lightness of `LCh` and `OKLCh` as well.
+/// - `LchC`, `LchH`: Chroma and hue of `LCh` and `OKLCh` (`C`, `h`).
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{Channel, ColorFormat};
+///
+/// assert_eq!(ColorFormat::BGRA.channels(), &[Channel::B, Channel::G, Channel::R, Channel::A]);
+/// assert_eq!(Channel::LabA.letter(), 'a');
+/// ```
 #[derive(PartialEq, Debug, Clone, Copy)]
-enum Channel {
+pub enum Channel {
     R, G, B, A,
     Gray,
     C, M, Y, K,
     H, S, L,
+    CieX, CieY, CieZ,
+    LabL, LabA, LabB,
+    LchC, LchH,
+}
+
+impl Channel {
+    /// The letter of the channel, as used in swizzle patterns. See [`swizzle`].
+    pub fn letter(&self) -> char {
+        return match self {
+            Channel::R => 'R',
+            Channel::G => 'G',
+            Channel::B => 'B',
+            Channel::A => 'A',
+            Channel::Gray => 'Y',
+            Channel::C => 'C',
+            Channel::M => 'M',
+            Channel::Y => 'Y',
+            Channel::K => 'K',
+            Channel::H => 'H',
+            Channel::S => 'S',
+            Channel::L => 'L',
+            Channel::CieX => 'X',
+            Channel::CieY => 'Y',
+            Channel::CieZ => 'Z',
+            Channel::LabL => 'L',
+            Channel::LabA => 'a',
+            Channel::LabB => 'b',
+            Channel::LchC => 'C',
+            Channel::LchH => 'h',
+        };
+    }
 }
 
 /// The per-pixel channel order.
@@ -183,29 +425,54 @@ fn channel_order(format: ColorFormat) -> &'static [Channel] {
         ColorFormat::YMKCA => &[Channel::Y, Channel::M, Channel::K, Channel::C, Channel::A],
 
         ColorFormat::HSL => &[Channel::H, Channel::S, Channel::L],
-        ColorFormat::HLS => &[Channel::H, Channel::L, Channel::A],
+        ColorFormat::HLS => &[Channel::H, Channel::L, Channel::S],
         ColorFormat::SHL => &[Channel::S, Channel::H, Channel::L],
         ColorFormat::SLH => &[Channel::S, Channel::L, Channel::H],
         ColorFormat::LHS => &[Channel::L, Channel::H, Channel::S],
         ColorFormat::LSH => &[Channel::L, Channel::S, Channel::H],
         ColorFormat::HSLA => &[Channel::H, Channel::S, Channel::L, Channel::A],
-        ColorFormat::HLSA => &[Channel::H, Channel::L, Channel::A, Channel::A],
+        ColorFormat::HLSA => &[Channel::H, Channel::L, Channel::S, Channel::A],
         ColorFormat::SHLA => &[Channel::S, Channel::H, Channel::L, Channel::A],
         ColorFormat::SLHA => &[Channel::S, Channel::L, Channel::H, Channel::A],
         ColorFormat::LHSA => &[Channel::L, Channel::H, Channel::S, Channel::A],
         ColorFormat::LSHA => &[Channel::L, Channel::S, Channel::H, Channel::A],
+
+        ColorFormat::RGB565   => &[Channel::R, Channel::G, Channel::B],
+        ColorFormat::RGBA4444 => &[Channel::R, Channel::G, Channel::B, Channel::A],
+        ColorFormat::RGBA5551 => &[Channel::R, Channel::G, Channel::B, Channel::A],
+        ColorFormat::RGB10A2  => &[Channel::R, Channel::G, Channel::B, Channel::A],
+
+        ColorFormat::LinearRGB  => &[Channel::R, Channel::G, Channel::B],
+        ColorFormat::LinearRGBA => &[Channel::R, Channel::G, Channel::B, Channel::A],
+
+        ColorFormat::XYZ     => &[Channel::CieX, Channel::CieY, Channel::CieZ],
+        ColorFormat::XYZA    => &[Channel::CieX, Channel::CieY, Channel::CieZ, Channel::A],
+        ColorFormat::XYZD50  => &[Channel::CieX, Channel::CieY, Channel::CieZ],
+        ColorFormat::XYZD50A => &[Channel::CieX, Channel::CieY, Channel::CieZ, Channel::A],
+        ColorFormat::Lab     => &[Channel::LabL, Channel::LabA, Channel::LabB],
+        ColorFormat::LabA    => &[Channel::LabL, Channel::LabA, Channel::LabB, Channel::A],
+        ColorFormat::LCh     => &[Channel::LabL, Channel::LchC, Channel::LchH],
+        ColorFormat::LChA    => &[Channel::LabL, Channel::LchC, Channel::LchH, Channel::A],
+        ColorFormat::OKLab   => &[Channel::LabL, Channel::LabA, Channel::LabB],
+        ColorFormat::OKLabA  => &[Channel::LabL, Channel::LabA, Channel::LabB, Channel::A],
+        ColorFormat::OKLCh   => &[Channel::LabL, Channel::LchC, Channel::LchH],
+        ColorFormat::OKLChA  => &[Channel::LabL, Channel::LchC, Channel::LchH, Channel::A],
     }
 }
 
 /// Convert raw pixel bytes from one color model to another.
 ///
+/// Formats of the same family are converted by reordering channels, which is lossless.
+/// Formats of different families, e.g. `RGB` and `CMYK`, `HSL`, `Gray` or `LinearRGB`, are converted through `sRGB`,
+/// with each channel rounded to the nearest byte. `Gray` is the relative luminance, computed in linear light.
+///
 /// ## Parameters
-/// - `data`: input byte-slice, length must be a multiple of `from.channel_count()`
+/// - `data`: input byte-slice, length must be a multiple of `from.bytes_per_pixel()`
 /// - `from`: source color model (e.g. `ColorFormat::RGBA`)
 /// - `to`: destination color model (e.g. `ColorFormat::BGR`)
 ///
 /// ## Returns
-/// A `Result` containing either a new `Vec<u8>` whose length is `pixel_count * to.channel_count()`, or an `ColorFormatConverterError` 
+/// A `Result` containing either a new `Vec<u8>` whose length is `pixel_count * to.bytes_per_pixel()`, or an `ColorFormatConverterError` 
 /// if the conversion failed, if the input length isn’t a multiple of the source channel count, or if the source and destination 
 /// formats are the same.
 ///
@@ -229,42 +496,329 @@ fn channel_order(format: ColorFormat) -> &'static [Channel] {
 /// let two_rgb = convert_vec_color_model(&two_rgba, ColorFormat::RGBA, ColorFormat::RGB);
 /// assert_eq!(two_rgb, Ok(vec![1, 2, 3,  4, 5, 6]));
 /// 
+/// // Different families: RGB -> CMYK and HSL
+/// let rgb = vec![255, 0, 0];
+/// assert_eq!(convert_vec_color_model(&rgb, ColorFormat::RGB, ColorFormat::CMYK), Ok(vec![0, 255, 255, 0]));
+/// assert_eq!(convert_vec_color_model(&rgb, ColorFormat::RGB, ColorFormat::HSL), Ok(vec![0, 255, 128]));
+/// 
+/// // RGB -> linear-light RGB
+/// let mid = vec![128, 128, 128];
+/// assert_eq!(convert_vec_color_model(&mid, ColorFormat::RGB, ColorFormat::LinearRGB), Ok(vec![55, 55, 55]));
+/// 
 /// // Same format RGBA -> RGBA (returns `Err(ColorFormatConverterError::SameFormat)`)
 /// let rgba = vec![128, 55, 88, 255];
 /// let rgba_err = convert_vec_color_model(&rgba, ColorFormat::RGBA, ColorFormat::RGBA);
 /// assert_eq!(rgba_err, Err(ColorFormatConverterError::SameFormat));
 /// ```
 pub fn convert_vec_color_model(data: &[u8], from: ColorFormat, to: ColorFormat) -> Result<Vec<u8>, ColorFormatConverterError> {
+    return convert_vec_color_model_generic(data, from, to);
+}
+
+/// Convert raw pixel channel values of any [`ChannelType`] from one color model to another.
+///
+/// Works exactly like [`convert_vec_color_model`], but for `u16` and `f32` channels as well as `u8`.
+/// A missing alpha channel is set to `ChannelType::MAX`, meaning `255`, `65535` or `1.0`.
+/// Float inputs are checked, and must not be `NaN` or outside of `0.0-1.0`, except for the device-independent formats
+/// (e.g. `Lab`), which can only be used with `f32` channels and hold their values as they are.
+///
+/// ## Parameters
+/// - `data`: input channel values, length must be a multiple of `from.channel_count()`
+/// - `from`: source color model (e.g. `ColorFormat::RGBA`)
+/// - `to`: destination color model (e.g. `ColorFormat::BGR`)
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<T>` whose length is `pixel_count * to.channel_count()`, or an
+/// [`ColorFormatConverterError`] if the input length isn’t a multiple of the source channel count, the source and
+/// destination formats are the same, or a float input is `NaN` (`NotANumber`) or out of range (`OutOfRange`).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_vec_color_model, convert_vec_color_model_generic, ColorFormat, ColorFormatConverterError};
+///
+/// // 16-bit RGB -> BGRA
+/// let rgb16: Vec<u16> = vec![1000, 2000, 3000];
+/// let bgra16 = convert_vec_color_model_generic(&rgb16, ColorFormat::RGB, ColorFormat::BGRA);
+/// assert_eq!(bgra16, Ok(vec![3000, 2000, 1000, 65535]));
+///
+/// // Float RGBA -> RGB
+/// let rgba32: Vec<f32> = vec![0.25, 0.5, 0.75, 1.0];
+/// let rgb32 = convert_vec_color_model_generic(&rgba32, ColorFormat::RGBA, ColorFormat::RGB);
+/// assert_eq!(rgb32, Ok(vec![0.25, 0.5, 0.75]));
+///
+/// // Float RGB -> CIE L*a*b* (which only fits in float channels)
+/// let red: Vec<f32> = vec![1.0, 0.0, 0.0];
+/// let lab = convert_vec_color_model_generic(&red, ColorFormat::RGB, ColorFormat::Lab).unwrap();
+/// assert!((lab[0] - 54.29).abs() < 0.05 && (lab[1] - 80.80).abs() < 0.05 && (lab[2] - 69.89).abs() < 0.05);
+/// 
+/// let lab_err = convert_vec_color_model(&[255, 0, 0], ColorFormat::RGB, ColorFormat::Lab);
+/// assert_eq!(lab_err, Err(ColorFormatConverterError::UnsupportedFormat));
+///
+/// // NaN input
+/// let nan: Vec<f32> = vec![f32::NAN, 0.5, 0.75];
+/// let nan_err = convert_vec_color_model_generic(&nan, ColorFormat::RGB, ColorFormat::BGR);
+/// assert_eq!(nan_err, Err(ColorFormatConverterError::NotANumber));
+/// ```
+pub fn convert_vec_color_model_generic<T: ChannelType>(data: &[T], from: ColorFormat, to: ColorFormat) -> Result<Vec<T>, ColorFormatConverterError> {
+    check_input(data, from, to)?;
+
+    let pixel_count = data.len() / from.bytes_per_pixel();
+    let mut out = Vec::with_capacity(pixel_count * to.bytes_per_pixel());
+
+    convert_row(data, from, to, &mut out)?;
+
+    return Ok(out);
+}
+
+/// Convert raw pixel channel values from one color model to another, writing into an existing buffer.
+///
+/// Works exactly like [`convert_vec_color_model_generic`], but nothing is allocated for the result, e.g. for converting
+/// frames in a render loop. Reordering the channels of one color family (e.g. `RGBA` to `BGR`) allocates nothing at all,
+/// other conversions use a small temporary buffer of a few thousand values. On error, `out` may be partly written.
+///
+/// ## Parameters
+/// - `data`: input channel values, length must be a multiple of `from.bytes_per_pixel()`
+/// - `out`: output buffer, length must be exactly `pixel_count * to.bytes_per_pixel()`
+/// - `from`: source color model (e.g. `ColorFormat::RGBA`)
+/// - `to`: destination color model (e.g. `ColorFormat::BGR`)
+///
+/// ## Returns
+/// An empty `Result`, or `InvalidOutputLength` if `out` has the wrong length, or any of the errors of [`convert_vec_color_model_generic`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_into, ColorFormat, ColorFormatConverterError};
+///
+/// let rgba: Vec<u8> = vec![10, 20, 30, 255,  40, 50, 60, 128];
+/// let mut bgr = vec![0; 6];
+/// convert_into(&rgba, &mut bgr, ColorFormat::RGBA, ColorFormat::BGR).unwrap();
+/// assert_eq!(bgr, vec![30, 20, 10,  60, 50, 40]);
+///
+/// // The same buffer can be reused for the next frame
+/// let rgba: Vec<u8> = vec![0, 0, 0, 255,  255, 255, 255, 255];
+/// convert_into(&rgba, &mut bgr, ColorFormat::RGBA, ColorFormat::BGR).unwrap();
+/// assert_eq!(bgr, vec![0, 0, 0,  255, 255, 255]);
+///
+/// let mut short = vec![0; 5];
+/// let err = convert_into(&rgba, &mut short, ColorFormat::RGBA, ColorFormat::BGR);
+/// assert_eq!(err, Err(ColorFormatConverterError::InvalidOutputLength));
+/// ```
+pub fn convert_into<T: ChannelType>(data: &[T], out: &mut [T], from: ColorFormat, to: ColorFormat) -> Result<(), ColorFormatConverterError> {
+    check_input(data, from, to)?;
+
+    let in_bytes = from.bytes_per_pixel();
+    let out_bytes = to.bytes_per_pixel();
+    if out.len() != data.len() / in_bytes * out_bytes {
+        return Err(ColorFormatConverterError::InvalidOutputLength);
+    }
+
+    if let Some(map) = swizzle_map::<T>(from, to) {
+        map.apply_into(data, out);
+        return Ok(());
+    }
+
+    let mut block = Vec::with_capacity(INTO_BLOCK_PIXELS * out_bytes);
+    for (input, output) in data.chunks(INTO_BLOCK_PIXELS * in_bytes).zip(out.chunks_mut(INTO_BLOCK_PIXELS * out_bytes)) {
+        block.clear();
+        convert_row(input, from, to, &mut block)?;
+        output.copy_from_slice(&block);
+    }
+    return Ok(());
+}
+
+/// Reorder the channels of a buffer in place, for formats of the same color family and size,
+/// such as `RGBA` and `BGRA`, or any two `CMYK` permutations.
+///
+/// ## Parameters
+/// - `data`: channel values in the `from` format, converted to the `to` format in place
+/// - `from`: source color model (e.g. `ColorFormat::RGBA`)
+/// - `to`: destination color model with the same channels in another order (e.g. `ColorFormat::BGRA`)
+///
+/// ## Returns
+/// An empty `Result`, or a [`ColorFormatConverterError`]: `UnsupportedFormat` if the formats don't have the same channels
+/// (including the packed formats), or any of the errors of [`convert_vec_color_model_generic`]. On error, `data` is unchanged.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_in_place, ColorFormat, ColorFormatConverterError};
+///
+/// let mut pixels: Vec<u8> = vec![10, 20, 30, 255,  40, 50, 60, 128];
+/// convert_in_place(&mut pixels, ColorFormat::RGBA, ColorFormat::BGRA).unwrap();
+/// assert_eq!(pixels, vec![30, 20, 10, 255,  60, 50, 40, 128]);
+///
+/// let mut ink: Vec<u8> = vec![1, 2, 3, 4];
+/// convert_in_place(&mut ink, ColorFormat::CMYK, ColorFormat::KCMY).unwrap();
+/// assert_eq!(ink, vec![4, 1, 2, 3]);
+///
+/// // RGBA and RGB don't have the same size
+/// let err = convert_in_place(&mut pixels, ColorFormat::RGBA, ColorFormat::RGB);
+/// assert_eq!(err, Err(ColorFormatConverterError::UnsupportedFormat));
+/// ```
+pub fn convert_in_place<T: ChannelType>(data: &mut [T], from: ColorFormat, to: ColorFormat) -> Result<(), ColorFormatConverterError> {
+    check_input(data, from, to)?;
+    let map = match swizzle_map::<T>(from, to) {
+        Some(map) if from.bytes_per_pixel() == to.bytes_per_pixel() => map,
+        _ => return Err(ColorFormatConverterError::UnsupportedFormat),
+    };
+    map.apply_in_place(data);
+    return Ok(());
+}
+
+/// Convert raw pixel bytes from one color model to another, for buffers whose rows are padded.
+///
+/// Works like [`convert_vec_color_model`], but the input is read row by row, `in_stride` bytes apart, and the
+/// output is written row by row, `out_stride` bytes apart. Padding bytes of the input are never read, and padding
+/// bytes of the output are set to `0`. The last input row doesn't need to be padded, since many buffers end right
+/// after their last pixel. Unlike [`convert_vec_color_model`], `from` and `to` may be the same format, which simply
+/// changes the stride. To get a stride for a given row alignment, use [`aligned_stride()`].
+///
+/// ## Parameters
+/// - `data`: input byte-slice
+/// - `from`: source color model (e.g. `ColorFormat::RGBA`)
+/// - `to`: destination color model (e.g. `ColorFormat::BGR`)
+/// - `width`: width of the image in pixels
+/// - `height`: height of the image in pixels
+/// - `in_stride`: distance in bytes between the start of two input rows, at least `width * from.bytes_per_pixel()`
+/// - `out_stride`: distance in bytes between the start of two output rows, at least `width * to.bytes_per_pixel()`
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<u8>` whose length is `height * out_stride`, or a [`ColorFormatConverterError`]:
+/// `InvalidDimensions` if the width or height are `0` or a stride is smaller than a row, and `BufferTooSmall`
+/// if `data` doesn't hold `height` rows of the given stride.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_vec_color_model_strided, aligned_stride, ColorFormat, ColorFormatConverterError};
+///
+/// // 2x2 RGBA with 4 bytes of padding per row -> tightly packed BGR
+/// let rgba = vec![
+///     1, 2, 3, 255,  4, 5, 6, 255,  0, 0, 0, 0,
+///     7, 8, 9, 255,  10, 11, 12, 255,  0, 0, 0, 0,
+/// ];
+/// let bgr = convert_vec_color_model_strided(&rgba, ColorFormat::RGBA, ColorFormat::BGR, 2, 2, 12, 6);
+/// assert_eq!(bgr, Ok(vec![3, 2, 1,  6, 5, 4,  9, 8, 7,  12, 11, 10]));
+///
+/// // RGB rows aligned to 4 bytes, as expected by e.g. BMP or OpenGL
+/// let rgb = vec![1, 2, 3,  4, 5, 6,  7, 8, 9,  10, 11, 12];
+/// let stride = aligned_stride(2, ColorFormat::RGB, 4);
+/// assert_eq!(stride, 8);
+/// let padded = convert_vec_color_model_strided(&rgb, ColorFormat::RGB, ColorFormat::RGB, 2, 2, 6, stride);
+/// assert_eq!(padded, Ok(vec![1, 2, 3, 4, 5, 6, 0, 0,  7, 8, 9, 10, 11, 12, 0, 0]));
+///
+/// // Buffer too small for 3 rows
+/// let err = convert_vec_color_model_strided(&rgb, ColorFormat::RGB, ColorFormat::RGB, 2, 3, 6, 6);
+/// assert_eq!(err, Err(ColorFormatConverterError::BufferTooSmall));
+/// ```
+pub fn convert_vec_color_model_strided(
+    data: &[u8],
+    from: ColorFormat,
+    to: ColorFormat,
+    width: usize,
+    height: usize,
+    in_stride: usize,
+    out_stride: usize
+) -> Result<Vec<u8>, ColorFormatConverterError> {
+
+    let in_row = width * from.bytes_per_pixel();
+    let out_row = width * to.bytes_per_pixel();
+
+    if width == 0 || height == 0 || in_stride < in_row || out_stride < out_row {
+        return Err(ColorFormatConverterError::InvalidDimensions);
+    }
+    if data.len() < in_stride * (height - 1) + in_row {
+        return Err(ColorFormatConverterError::BufferTooSmall);
+    }
+
+    let mut out = Vec::with_capacity(out_stride * height);
+
+    for y in 0..height {
+        let row = &data[y * in_stride..y * in_stride + in_row];
+        if from == to { out.extend_from_slice(row); }
+        else { convert_row(row, from, to, &mut out)?; }
+        out.resize((y + 1) * out_stride, 0);
+    }
+
+    return Ok(out);
+}
+
+/// Returns the smallest row stride in bytes for `width` pixels of `format`, that is a multiple of `alignment`.
+/// An `alignment` of `0` or `1` means no alignment.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{aligned_stride, ColorFormat};
+///
+/// assert_eq!(aligned_stride(3, ColorFormat::RGB, 4), 12);
+/// assert_eq!(aligned_stride(5, ColorFormat::RGB, 4), 16);
+/// assert_eq!(aligned_stride(5, ColorFormat::RGBA, 256), 256);
+/// assert_eq!(aligned_stride(5, ColorFormat::Gray, 0), 5);
+/// ```
+pub fn aligned_stride(width: usize, format: ColorFormat, alignment: usize) -> usize {
+    let row = width * format.bytes_per_pixel();
+    if alignment <= 1 { return row; }
+    return row.div_ceil(alignment) * alignment;
+}
+
+/// Pixels converted at a time by [`convert_into`] between color families.
+const INTO_BLOCK_PIXELS: usize = 1024;
+
+/// Checks the formats, length and values of an input buffer for a conversion.
+fn check_input<T: ChannelType>(data: &[T], from: ColorFormat, to: ColorFormat) -> Result<(), ColorFormatConverterError> {
     if from == to { return Err(ColorFormatConverterError::SameFormat) }
-    if data.len() < 3 { return Err(ColorFormatConverterError::InvalidInputLength) }
-
-    let from_model = channel_order(from);
-    let to_model = channel_order(to);
-
-    let i_channels_num = if from as usize % 2 == 0 {4} else {3};
-    let o_channels_num = if to as usize % 2 == 0 {4} else {3};
-
-    let pixel_count = data.len() / i_channels_num;
-    let mut out = Vec::with_capacity(pixel_count * o_channels_num);
-
-    for pix in 0..pixel_count {
-        let base = pix * i_channels_num;
-        for &ch in to_model {
-            let byte = match from_model.iter().position(|&c| c == ch) {
-                Some(idx) => data[base + idx],
-                None => {
-                    // channel not in source: if it's alpha, default to 255; else error
-                    match ch {
-                        Channel::A => 255,
-                        _ => unreachable!("This should never happen due to prior checks."),
-                    }
-                }
-            };
-            out.push(byte);
+    if data.is_empty() || !data.len().is_multiple_of(from.bytes_per_pixel()) { return Err(ColorFormatConverterError::InvalidInputLength) }
+    if T::IS_FLOAT {
+        check_values(data, from)?;
+    }
+    return Ok(());
+}
+
+/// Checks float channel values: none may be `NaN`, and they must be within `0.0-1.0` unless the format is unbounded.
+fn check_values<T: ChannelType>(data: &[T], format: ColorFormat) -> Result<(), ColorFormatConverterError> {
+    if models::family(format).is_unbounded() {
+        if data.iter().any(|v| v.to_normalized().is_nan()) { return Err(ColorFormatConverterError::NotANumber); }
+        return Ok(());
+    }
+    for &value in data { value.check()?; }
+    return Ok(());
+}
+
+/// The channel mapping of two formats, if converting between them only reorders channels.
+fn swizzle_map<T: ChannelType>(from: ColorFormat, to: ColorFormat) -> Option<swizzle::SwizzleMap> {
+    let family = models::family(from);
+    if from.is_packed() || to.is_packed() || family != models::family(to) || (!T::IS_FLOAT && family.is_unbounded()) {
+        return None;
+    }
+    return Some(swizzle::SwizzleMap::new(channel_order(from), channel_order(to)));
+}
+
+/// Converts every pixel in `data`, appending the result to `out`.
+fn convert_row<T: ChannelType>(data: &[T], from: ColorFormat, to: ColorFormat, out: &mut Vec<T>) -> Result<(), ColorFormatConverterError> {
+    let from_family = models::family(from);
+    let to_family = models::family(to);
+
+    if !T::IS_FLOAT && (from_family.is_unbounded() || to_family.is_unbounded()) {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    let rgb_like = |family| matches!(family, models::Family::Rgb | models::Family::Packed);
+
+    if from.is_packed() || to.is_packed() {
+        if rgb_like(from_family) && rgb_like(to_family) {
+            return packed::convert_packed(data, from, to, None, out);
+        }
+        // packed to another family (or the other way around) goes through RGBA
+        let mut rgba = Vec::new();
+        if from.is_packed() {
+            packed::convert_packed(data, from, ColorFormat::RGBA, None, &mut rgba)?;
+            return convert_row(&rgba, ColorFormat::RGBA, to, out);
         }
+        convert_row(data, from, ColorFormat::RGBA, &mut rgba)?;
+        return packed::convert_packed(&rgba, ColorFormat::RGBA, to, None, out);
     }
 
-    return Ok(out);
+    if from_family != to_family {
+        return models::convert_models(data, from, to, out);
+    }
+    swizzle::SwizzleMap::new(channel_order(from), channel_order(to)).apply(data, out);
+    return Ok(());
 }
 
 /// Convert raw pixel bytes from `RGB` color model to `CMYK`.
@@ -273,6 +827,7 @@ pub fn convert_vec_color_model(data: &[u8], from: ColorFormat, to: ColorFormat)
 /// an integer-math implementation. I haven't really benchmarked it though.
 /// However, it is more accurate than an integer-math implementation.
 /// For an integer-math, slightly inaccurate conversion, use [`from_rgb_to_cmyk_integer()`].
+/// This always uses as much black as possible, for other black generation and ink limits use [`from_rgb_to_cmyk_separated()`].
 ///
 /// ## Parameters
 /// - `r`: Red channel value `(0-255)`
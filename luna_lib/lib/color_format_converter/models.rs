@@ -0,0 +1,309 @@
+//! Conversion between color model families, e.g. from `RGB` to `CMYK` or `HSL`.
+//!
+//! Pixels are decoded from their family into normalized, `sRGB` encoded `RGBA`, and then encoded into the
+//! destination family. Conversions within a family only reorder channels, and don't pass through here.
+
+use super::spaces::{self, Triplet, WhitePoint};
+use super::{channel_order, check_values, packed, Channel, ChannelType, ColorFormat, ColorFormatConverterError, TransferFunction};
+
+/// The color model families, formats within the same family only differ in channel order and alpha.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(super) enum Family {
+    Rgb,
+    LinearRgb,
+    Gray,
+    Cmyk,
+    Hsl,
+    Packed,
+    Xyz(WhitePoint),
+    Lab,
+    Lch,
+    OkLab,
+    OkLch,
+}
+
+impl Family {
+    /// Whether the family's channel values aren't limited to `0.0-1.0`, so they can only be held by float channels.
+    pub(super) fn is_unbounded(&self) -> bool {
+        return matches!(self, Family::Xyz(_) | Family::Lab | Family::Lch | Family::OkLab | Family::OkLch);
+    }
+}
+
+pub(super) fn family(format: ColorFormat) -> Family {
+    if format.is_packed() { return Family::Packed; }
+    return match format {
+        ColorFormat::LinearRGB | ColorFormat::LinearRGBA => Family::LinearRgb,
+        ColorFormat::XYZ | ColorFormat::XYZA => Family::Xyz(WhitePoint::D65),
+        ColorFormat::XYZD50 | ColorFormat::XYZD50A => Family::Xyz(WhitePoint::D50),
+        ColorFormat::Lab | ColorFormat::LabA => Family::Lab,
+        ColorFormat::LCh | ColorFormat::LChA => Family::Lch,
+        ColorFormat::OKLab | ColorFormat::OKLabA => Family::OkLab,
+        ColorFormat::OKLCh | ColorFormat::OKLChA => Family::OkLch,
+        _ => match channel_order(format)[0] {
+            Channel::R | Channel::G | Channel::B => Family::Rgb,
+            Channel::Gray => Family::Gray,
+            Channel::C | Channel::M | Channel::Y | Channel::K => Family::Cmyk,
+            Channel::H | Channel::S | Channel::L => Family::Hsl,
+            _ => unreachable!("All other formats are matched above."),
+        },
+    };
+}
+
+/// The `sRGB` encoded counterpart of a `LinearRGB(A)` format, for the byte-level `RGB` code paths
+/// (the `YUV` layouts and dithered packing), which convert linear-light data through it.
+pub(super) fn encoded_rgb(format: ColorFormat) -> Option<ColorFormat> {
+    return match format {
+        ColorFormat::LinearRGB => Some(ColorFormat::RGB),
+        ColorFormat::LinearRGBA => Some(ColorFormat::RGBA),
+        _ => None,
+    };
+}
+
+/// Number of `Channel` variants, used to index channel values by `Channel as usize`.
+const CHANNELS: usize = Channel::LchH as usize + 1;
+
+/// Relative luminance weights of linear `sRGB`.
+pub(super) const LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Decodes a pixel of any non-packed format into normalized, `sRGB` encoded `RGBA`.
+pub(super) fn decode_pixel<T: ChannelType>(pixel: &[T], format: ColorFormat) -> [f32; 4] {
+    let mut values = [0.0; CHANNELS];
+    values[Channel::A as usize] = 1.0;
+    for (&value, &ch) in pixel.iter().zip(channel_order(format).iter()) {
+        values[ch as usize] = value.to_normalized();
+    }
+    let get = |ch: Channel| values[ch as usize];
+    let alpha = get(Channel::A);
+
+    return match family(format) {
+        Family::Rgb | Family::Packed => [get(Channel::R), get(Channel::G), get(Channel::B), alpha],
+        Family::LinearRgb => {
+            let srgb = TransferFunction::SRGB;
+            [srgb.encode(get(Channel::R)), srgb.encode(get(Channel::G)), srgb.encode(get(Channel::B)), alpha]
+        },
+        Family::Gray => [get(Channel::Gray), get(Channel::Gray), get(Channel::Gray), alpha],
+        Family::Cmyk => {
+            let k = get(Channel::K);
+            [(1.0 - get(Channel::C)) * (1.0 - k), (1.0 - get(Channel::M)) * (1.0 - k), (1.0 - get(Channel::Y)) * (1.0 - k), alpha]
+        },
+        Family::Hsl => {
+            let (r, g, b) = hsl_to_rgb(get(Channel::H) * 360.0, get(Channel::S), get(Channel::L));
+            [r, g, b, alpha]
+        },
+        Family::Xyz(white) => {
+            let xyz = spaces::adapt([get(Channel::CieX), get(Channel::CieY), get(Channel::CieZ)], white, WhitePoint::D65);
+            let [r, g, b] = spaces::linear_to_srgb(spaces::xyz_to_linear_srgb(xyz));
+            [r, g, b, alpha]
+        },
+        Family::Lab => {
+            let [r, g, b] = spaces::lab_to_srgb([get(Channel::LabL), get(Channel::LabA), get(Channel::LabB)]);
+            [r, g, b, alpha]
+        },
+        Family::Lch => {
+            let [r, g, b] = spaces::lab_to_srgb(spaces::from_polar([get(Channel::LabL), get(Channel::LchC), get(Channel::LchH)]));
+            [r, g, b, alpha]
+        },
+        Family::OkLab => {
+            let [r, g, b] = spaces::oklab_to_srgb([get(Channel::LabL), get(Channel::LabA), get(Channel::LabB)]);
+            [r, g, b, alpha]
+        },
+        Family::OkLch => {
+            let [r, g, b] = spaces::oklab_to_srgb(spaces::from_polar([get(Channel::LabL), get(Channel::LchC), get(Channel::LchH)]));
+            [r, g, b, alpha]
+        },
+    };
+}
+
+/// Encodes normalized, `sRGB` encoded `RGBA` into a pixel of any non-packed format, appending it to `out`.
+pub(super) fn encode_pixel<T: ChannelType>(rgba: [f32; 4], format: ColorFormat, out: &mut Vec<T>) {
+    let [r, g, b, a] = rgba;
+    let mut values = [0.0; CHANNELS];
+    values[Channel::A as usize] = a;
+
+    let mut set = |ch: Channel, value: f32| values[ch as usize] = value;
+    match family(format) {
+        Family::Rgb | Family::Packed => {
+            set(Channel::R, r);
+            set(Channel::G, g);
+            set(Channel::B, b);
+        },
+        Family::LinearRgb => {
+            let srgb = TransferFunction::SRGB;
+            set(Channel::R, srgb.decode(r));
+            set(Channel::G, srgb.decode(g));
+            set(Channel::B, srgb.decode(b));
+        },
+        Family::Gray => {
+            // luminance is computed in linear light, so e.g. pure red isn't darker than it looks
+            let srgb = TransferFunction::SRGB;
+            let y = LUMINANCE[0] * srgb.decode(r) + LUMINANCE[1] * srgb.decode(g) + LUMINANCE[2] * srgb.decode(b);
+            set(Channel::Gray, srgb.encode(y));
+        },
+        Family::Cmyk => {
+            let k = 1.0 - r.max(g).max(b);
+            if k < 1.0 {
+                set(Channel::C, (1.0 - r - k) / (1.0 - k));
+                set(Channel::M, (1.0 - g - k) / (1.0 - k));
+                set(Channel::Y, (1.0 - b - k) / (1.0 - k));
+            }
+            set(Channel::K, k);
+        },
+        Family::Hsl => {
+            let (h, s, l) = rgb_to_hsl(r, g, b);
+            set(Channel::H, h / 360.0);
+            set(Channel::S, s);
+            set(Channel::L, l);
+        },
+        Family::Xyz(white) => {
+            let xyz = spaces::adapt(spaces::linear_srgb_to_xyz(spaces::srgb_to_linear([r, g, b])), WhitePoint::D65, white);
+            set(Channel::CieX, xyz[0]);
+            set(Channel::CieY, xyz[1]);
+            set(Channel::CieZ, xyz[2]);
+        },
+        Family::Lab | Family::OkLab | Family::Lch | Family::OkLch => {
+            let lab = match family(format) {
+                Family::Lab | Family::Lch => spaces::srgb_to_lab([r, g, b]),
+                _ => spaces::srgb_to_oklab([r, g, b]),
+            };
+            let lch = spaces::to_polar(lab);
+            set(Channel::LabL, lab[0]);
+            set(Channel::LabA, lab[1]);
+            set(Channel::LabB, lab[2]);
+            set(Channel::LchC, lch[1]);
+            set(Channel::LchH, lch[2]);
+        },
+    }
+
+    for &ch in channel_order(format) {
+        out.push(T::from_normalized(values[ch as usize]));
+    }
+}
+
+/// Converts every pixel in `data` between two different, non-packed families, appending the result to `out`.
+pub(super) fn convert_models<T: ChannelType>(
+    data: &[T],
+    from: ColorFormat,
+    to: ColorFormat,
+    out: &mut Vec<T>
+) -> Result<(), ColorFormatConverterError> {
+
+    let channels = from.channel_count();
+    if !data.len().is_multiple_of(channels) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    for pixel in data.chunks_exact(channels) {
+        encode_pixel(decode_pixel(pixel, from), to, out);
+    }
+    return Ok(());
+}
+
+/// Decodes a single pixel of any format into normalized, `sRGB` encoded `RGBA`, after checking its length and values.
+pub(super) fn decode_color<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<[f32; 4], ColorFormatConverterError> {
+    if pixel.len() != format.bytes_per_pixel() {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    if !T::IS_FLOAT && family(format).is_unbounded() {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    if T::IS_FLOAT {
+        check_values(pixel, format)?;
+    }
+
+    if format.is_packed() {
+        let mut rgba = Vec::with_capacity(4);
+        packed::convert_packed(pixel, format, ColorFormat::RGBA, None, &mut rgba)?;
+        return Ok([rgba[0].to_normalized(), rgba[1].to_normalized(), rgba[2].to_normalized(), rgba[3].to_normalized()]);
+    }
+    return Ok(decode_pixel(pixel, format));
+}
+
+/// Decodes a single pixel of any format into `CIE L*a*b*` relative to `D50`, skipping `sRGB` for `Lab` and `LCh` pixels.
+pub(super) fn decode_lab<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<Triplet, ColorFormatConverterError> {
+    let rgba = decode_color(pixel, format)?;
+    let values = |a: usize, b: usize, c: usize| [pixel[a].to_normalized(), pixel[b].to_normalized(), pixel[c].to_normalized()];
+    return Ok(match format {
+        ColorFormat::Lab | ColorFormat::LabA => values(0, 1, 2),
+        ColorFormat::LCh | ColorFormat::LChA => spaces::from_polar(values(0, 1, 2)),
+        _ => spaces::srgb_to_lab([rgba[0], rgba[1], rgba[2]]),
+    });
+}
+
+/// Decodes a single pixel of any format into `OKLab`, skipping `sRGB` for `OKLab` and `OKLCh` pixels.
+pub(super) fn decode_oklab<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<Triplet, ColorFormatConverterError> {
+    let rgba = decode_color(pixel, format)?;
+    let values = |a: usize, b: usize, c: usize| [pixel[a].to_normalized(), pixel[b].to_normalized(), pixel[c].to_normalized()];
+    return Ok(match format {
+        ColorFormat::OKLab | ColorFormat::OKLabA => values(0, 1, 2),
+        ColorFormat::OKLCh | ColorFormat::OKLChA => spaces::from_polar(values(0, 1, 2)),
+        _ => spaces::srgb_to_oklab([rgba[0], rgba[1], rgba[2]]),
+    });
+}
+
+/// Normalized `RGB` to hue in degrees `(0-360)`, saturation and lightness `(0-1)`.
+pub(super) fn rgb_to_hsl(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let l = (max + min) / 2.0;
+
+    if delta <= 0.0 { return (0.0, 0.0, l); }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r { ((g - b) / delta).rem_euclid(6.0) }
+        else if max == g { (b - r) / delta + 2.0 }
+        else { (r - g) / delta + 4.0 };
+
+    return (h * 60.0, s.min(1.0), l);
+}
+
+/// Hue in degrees, saturation and lightness `(0-1)` to normalized `RGB`.
+pub(super) fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (f32, f32, f32) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    return (r + m, g + m, b + m);
+}
+
+/// Applies `f` to every pixel of `data` in normalized, `sRGB` encoded `RGBA`, keeping the buffer in `format`.
+pub(super) fn map_colors<T: ChannelType>(
+    data: &[T],
+    format: ColorFormat,
+    f: impl Fn([f32; 4]) -> [f32; 4]
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    let channels = format.bytes_per_pixel();
+    if data.is_empty() || !data.len().is_multiple_of(channels) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    if !T::IS_FLOAT && family(format).is_unbounded() {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    if T::IS_FLOAT {
+        check_values(data, format)?;
+    }
+
+    if format.is_packed() {
+        let mut rgba = Vec::with_capacity(data.len() / channels * 4);
+        packed::convert_packed(data, format, ColorFormat::RGBA, None, &mut rgba)?;
+        let rgba = map_colors(&rgba, ColorFormat::RGBA, f)?;
+        let mut out = Vec::with_capacity(data.len());
+        packed::convert_packed(&rgba, ColorFormat::RGBA, format, None, &mut out)?;
+        return Ok(out);
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    for pixel in data.chunks_exact(channels) {
+        encode_pixel(f(decode_pixel(pixel, format)), format, &mut out);
+    }
+    return Ok(out);
+}
@@ -0,0 +1,211 @@
+//! Packed pixel formats, where the channels of a pixel share a single 16-bit or 32-bit little-endian word,
+//! as used by embedded displays and GPU texture formats.
+
+use super::models::{self, Family};
+use super::{channel_order, convert_vec_color_model, Channel, ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// Bit layout of a packed format: the size of a pixel in bytes, and the `(shift, bits)` of the `R`, `G`, `B` and `A` fields.
+struct PackedLayout {
+    bytes: usize,
+    fields: [(u32, u32); 4],
+}
+
+fn packed_layout(format: ColorFormat) -> Option<PackedLayout> {
+    return match format {
+        ColorFormat::RGB565   => Some(PackedLayout { bytes: 2, fields: [(11, 5), (5, 6), (0, 5), (0, 0)] }),
+        ColorFormat::RGBA4444 => Some(PackedLayout { bytes: 2, fields: [(12, 4), (8, 4), (4, 4), (0, 4)] }),
+        ColorFormat::RGBA5551 => Some(PackedLayout { bytes: 2, fields: [(11, 5), (6, 5), (1, 5), (0, 1)] }),
+        ColorFormat::RGB10A2  => Some(PackedLayout { bytes: 4, fields: [(0, 10), (10, 10), (20, 10), (30, 2)] }),
+        _ => None,
+    };
+}
+
+/// Returns `true` if the format is one of the packed formats.
+pub(super) fn is_packed(format: ColorFormat) -> bool {
+    return packed_layout(format).is_some();
+}
+
+/// The size of a single packed pixel in bytes, or `None` if the format isn't packed.
+pub(super) fn packed_bytes(format: ColorFormat) -> Option<usize> {
+    return packed_layout(format).map(|layout| layout.bytes);
+}
+
+impl PackedLayout {
+    /// Unpacks a word into normalized `RGBA`, alpha is `1.0` if the format doesn't have an alpha field.
+    fn unpack(&self, word: u32) -> [f32; 4] {
+        let mut rgba = [1.0; 4];
+        for (value, &(shift, bits)) in rgba.iter_mut().zip(self.fields.iter()) {
+            if bits == 0 { continue; }
+            let max = (1u32 << bits) - 1;
+            *value = ((word >> shift) & max) as f32 / max as f32;
+        }
+        return rgba;
+    }
+
+    /// Packs normalized `RGBA` into a word, rounding each channel to the nearest level.
+    /// `offset` is added to each channel in units of one level, and is used for dithering.
+    fn pack(&self, rgba: [f32; 4], offset: f32) -> u32 {
+        let mut word = 0;
+        for (&value, &(shift, bits)) in rgba.iter().zip(self.fields.iter()) {
+            if bits == 0 { continue; }
+            let max = (1u32 << bits) - 1;
+            let level = (value * max as f32 + offset).round().clamp(0.0, max as f32) as u32;
+            word |= level << shift;
+        }
+        return word;
+    }
+
+    /// Reads the little-endian word of a pixel, one byte per value.
+    fn read<T: ChannelType>(&self, pixel: &[T]) -> u32 {
+        return pixel.iter().rev().fold(0, |word, &v| (word << 8) | u8::from_normalized(v.to_normalized()) as u32);
+    }
+
+    /// Writes a word as little-endian bytes, one byte per value.
+    fn write<T: ChannelType>(&self, word: u32, out: &mut Vec<T>) {
+        out.extend(word.to_le_bytes()[..self.bytes].iter().map(|b| T::from_normalized(b.to_normalized())));
+    }
+}
+
+/// Normalized `RGBA` of a pixel in one of the `RGB` and `RGBA` formats or their permutations.
+fn read_rgba<T: ChannelType>(pixel: &[T], order: &[Channel]) -> [f32; 4] {
+    let mut rgba = [0.0, 0.0, 0.0, 1.0];
+    for (&value, ch) in pixel.iter().zip(order.iter()) {
+        match ch {
+            Channel::R => rgba[0] = value.to_normalized(),
+            Channel::G => rgba[1] = value.to_normalized(),
+            Channel::B => rgba[2] = value.to_normalized(),
+            Channel::A => rgba[3] = value.to_normalized(),
+            _ => {},
+        }
+    }
+    return rgba;
+}
+
+fn write_rgba<T: ChannelType>(rgba: [f32; 4], order: &[Channel], out: &mut Vec<T>) {
+    for ch in order {
+        out.push(T::from_normalized(match ch {
+            Channel::R => rgba[0],
+            Channel::G => rgba[1],
+            Channel::B => rgba[2],
+            _ => rgba[3],
+        }));
+    }
+}
+
+/// Whether the format is one of the `sRGB` encoded `RGB` and `RGBA` formats or their permutations.
+fn is_rgb(format: ColorFormat) -> bool {
+    return models::family(format) == Family::Rgb;
+}
+
+/// 4x4 ordered dithering matrix.
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [ 0.0,  8.0,  2.0, 10.0],
+    [12.0,  4.0, 14.0,  6.0],
+    [ 3.0, 11.0,  1.0,  9.0],
+    [15.0,  7.0, 13.0,  5.0],
+];
+
+/// Converts pixels where `from`, `to` or both are packed formats.
+/// The other side must be one of the `RGB` and `RGBA` formats or their permutations.
+/// If `width` is given, packing is dithered with a 4x4 ordered dither, treating `data` as rows of `width` pixels.
+pub(super) fn convert_packed<T: ChannelType>(
+    data: &[T],
+    from: ColorFormat,
+    to: ColorFormat,
+    width: Option<usize>,
+    out: &mut Vec<T>
+) -> Result<(), ColorFormatConverterError> {
+
+    // packed pixels are stored as bytes, so other channel types can't hold them
+    if std::mem::size_of::<T>() != 1 || T::IS_FLOAT {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+
+    let from_layout = packed_layout(from);
+    let to_layout = packed_layout(to);
+
+    if (from_layout.is_none() && !is_rgb(from)) || (to_layout.is_none() && !is_rgb(to)) {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+
+    let from_size = from_layout.as_ref().map_or(from.channel_count(), |layout| layout.bytes);
+    if !data.len().is_multiple_of(from_size) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+
+    for (i, pixel) in data.chunks_exact(from_size).enumerate() {
+        let rgba = match &from_layout {
+            Some(layout) => layout.unpack(layout.read(pixel)),
+            None => read_rgba(pixel, channel_order(from)),
+        };
+
+        match &to_layout {
+            Some(layout) => {
+                let offset = match width {
+                    Some(w) => BAYER_4X4[(i / w) % 4][(i % w) % 4] / 16.0 - 0.46875,
+                    None => 0.0,
+                };
+                layout.write(layout.pack(rgba, offset), out);
+            },
+            None => write_rgba(rgba, channel_order(to), out),
+        }
+    }
+
+    return Ok(());
+}
+
+/// Convert raw pixel bytes into one of the packed formats, using ordered dithering.
+///
+/// Packing reduces every channel to a few bits, which turns smooth gradients into visible bands.
+/// This function spreads the rounding error over a 4x4 ordered (Bayer) pattern instead, which hides the banding
+/// at the cost of a fine, regular texture. For plain rounding, use [`convert_vec_color_model`](super::convert_vec_color_model).
+///
+/// ## Parameters
+/// - `data`: input byte-slice, `width` pixels per row
+/// - `from`: source color model, one of the `RGB` and `RGBA` formats or their permutations, `LinearRGB(A)`, or a packed format
+/// - `to`: destination packed format (`RGB565`, `RGBA4444`, `RGBA5551` or `RGB10A2`)
+/// - `width`: width of the image in pixels, needed to place the dither pattern
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<u8>` with the packed pixels, or a [`ColorFormatConverterError`]:
+/// `UnsupportedFormat` if `to` isn't packed or `from` isn't an `RGB(A)`, `LinearRGB(A)` or packed format, `InvalidDimensions` if `width` is `0`,
+/// and `InvalidInputLength` if `data` isn't made of whole rows.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_vec_color_model, convert_vec_color_model_dithered, ColorFormat};
+///
+/// // A flat color between two RGB565 levels is dithered into a mix of both
+/// let rgb = [136u8, 0, 0].repeat(4);
+/// let packed = convert_vec_color_model_dithered(&rgb, ColorFormat::RGB, ColorFormat::RGB565, 4).unwrap();
+/// let reds: Vec<u16> = packed.chunks(2).map(|p| u16::from_le_bytes([p[0], p[1]]) >> 11).collect();
+/// assert_eq!(reds, vec![16, 17, 16, 17]);
+///
+/// // Linear-light values are encoded to sRGB first, so linear 55 is a mid gray
+/// let linear = [55u8, 55, 55];
+/// let dithered = convert_vec_color_model_dithered(&linear, ColorFormat::LinearRGB, ColorFormat::RGB565, 1).unwrap();
+/// let srgb = convert_vec_color_model(&linear, ColorFormat::LinearRGB, ColorFormat::RGB).unwrap();
+/// assert_eq!(dithered, convert_vec_color_model_dithered(&srgb, ColorFormat::RGB, ColorFormat::RGB565, 1).unwrap());
+/// assert_eq!(u16::from_le_bytes([dithered[0], dithered[1]]) >> 11, 15);
+/// ```
+pub fn convert_vec_color_model_dithered(data: &[u8], from: ColorFormat, to: ColorFormat, width: usize) -> Result<Vec<u8>, ColorFormatConverterError> {
+    if !is_packed(to) { return Err(ColorFormatConverterError::UnsupportedFormat); }
+    if width == 0 { return Err(ColorFormatConverterError::InvalidDimensions); }
+    if !data.len().is_multiple_of(width * from.bytes_per_pixel()) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+
+    // linear-light values are encoded to sRGB before they are dithered
+    let encoded;
+    let (data, from) = match models::encoded_rgb(from) {
+        Some(rgb) => {
+            encoded = convert_vec_color_model(data, from, rgb)?;
+            (encoded.as_slice(), rgb)
+        },
+        None => (data, from),
+    };
+
+    let mut out = Vec::with_capacity(data.len() / from.bytes_per_pixel() * to.bytes_per_pixel());
+    convert_packed(data, from, to, Some(width), &mut out)?;
+    return Ok(out);
+}
@@ -0,0 +1,214 @@
+//! Palette generation: color harmonies, tints, shades and tones, and perceptual ramps.
+//!
+//! Colors generated in `OKLCH` or `OKLab` may fall outside of the `sRGB` gamut, in which case they are
+//! clipped when converted to integer channels, e.g. with [`Color::to_rgb8`].
+
+use super::Color;
+
+/// Color harmonies, sets of colors with hues at fixed angles around the color wheel.
+///
+/// - `Complementary`: The color and its opposite, at `180°`.
+/// - `Analogous`: The color and its neighbors, at `+30°` and `-30°`.
+/// - `Triadic`: Three colors evenly spaced, at `120°` and `240°`.
+/// - `Tetradic`: Four colors evenly spaced (a square), at `90°`, `180°` and `270°`.
+/// - `SplitComplementary`: The color and the two neighbors of its opposite, at `150°` and `210°`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Harmony {
+    Complementary,
+    Analogous,
+    Triadic,
+    Tetradic,
+    SplitComplementary,
+}
+
+impl Harmony {
+    /// The hue offsets of the harmony, in degrees, starting with the base color's `0°`.
+    pub fn offsets(&self) -> &'static [f32] {
+        return match self {
+            Harmony::Complementary => &[0.0, 180.0],
+            Harmony::Analogous => &[0.0, 30.0, 330.0],
+            Harmony::Triadic => &[0.0, 120.0, 240.0],
+            Harmony::Tetradic => &[0.0, 90.0, 180.0, 270.0],
+            Harmony::SplitComplementary => &[0.0, 150.0, 210.0],
+        };
+    }
+}
+
+/// The color model in which hues are rotated.
+///
+/// - `HSL`: The classic color wheel. Rotated colors keep their saturation and lightness, but not their perceived lightness,
+///   e.g. the complement of a bright yellow is a much darker blue.
+/// - `OKLCh`: A perceptual color wheel. Rotated colors keep their perceived lightness and chroma, but may fall outside of the `sRGB` gamut.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HueSpace {
+    HSL,
+    OKLCh,
+}
+
+/// Generate a color harmony, rotating the hue of the given color.
+///
+/// ## Parameters
+/// - `color`: the base color
+/// - `harmony`: the harmony to generate
+/// - `space`: the color model to rotate the hue in
+///
+/// ## Returns
+/// A `Vec` of colors, starting with the base color, in the order of [`Harmony::offsets`]. Alpha is kept.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{harmony, Color, Harmony, HueSpace};
+///
+/// let red: Color = "#ff0000".parse().unwrap();
+/// let triad: Vec<String> = harmony(red, Harmony::Triadic, HueSpace::HSL).iter().map(Color::to_hex).collect();
+/// assert_eq!(triad, vec!["#ff0000", "#00ff00", "#0000ff"]);
+///
+/// // In OKLCH, the complement keeps the perceived lightness and chroma
+/// let complement = harmony(red, Harmony::Complementary, HueSpace::OKLCh)[1];
+/// assert!((complement.to_oklch()[0] - red.to_oklch()[0]).abs() < 0.001);
+/// ```
+pub fn harmony(color: Color, harmony: Harmony, space: HueSpace) -> Vec<Color> {
+    return harmony.offsets().iter().map(|&offset| rotate_hue(color, offset, space)).collect();
+}
+
+/// Rotate the hue of a color by the given amount of degrees.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{rotate_hue, Color, HueSpace};
+///
+/// let yellow = rotate_hue(Color::from_rgb8(255, 0, 0), 60.0, HueSpace::HSL);
+/// assert_eq!(yellow.to_rgb8(), [255, 255, 0]);
+/// ```
+pub fn rotate_hue(color: Color, degrees: f32, space: HueSpace) -> Color {
+    if degrees == 0.0 {
+        return color;
+    }
+    return match space {
+        HueSpace::HSL => {
+            let [h, s, l] = color.to_hsl();
+            Color::from_hsl((h + degrees).rem_euclid(360.0), s, l, color.a)
+        },
+        HueSpace::OKLCh => {
+            let [l, c, h] = color.to_oklch();
+            Color::from_oklch(l, c, (h + degrees).rem_euclid(360.0), color.a)
+        },
+    };
+}
+
+/// Generate tints of a color, mixing it with increasing amounts of white.
+///
+/// Colors are mixed in `OKLab`, so the steps are perceptually even.
+///
+/// ## Parameters
+/// - `color`: the base color
+/// - `count`: the amount of colors to generate
+///
+/// ## Returns
+/// A `Vec` of `count` colors, starting with the base color and evenly approaching, but not reaching, white.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{tints, Color};
+///
+/// let steps = tints(Color::from_rgb8(0, 0, 255), 4);
+/// assert_eq!(steps.len(), 4);
+/// assert_eq!(steps[0].to_hex(), "#0000ff");
+/// assert!(steps.windows(2).all(|w| w[1].to_oklab()[0] > w[0].to_oklab()[0]));
+/// ```
+pub fn tints(color: Color, count: usize) -> Vec<Color> {
+    return mix_steps(color, Color::new(1.0, 1.0, 1.0, color.a), count);
+}
+
+/// Generate shades of a color, mixing it with increasing amounts of black.
+///
+/// ## Returns
+/// A `Vec` of `count` colors, starting with the base color and evenly approaching, but not reaching, black. See [`tints`].
+pub fn shades(color: Color, count: usize) -> Vec<Color> {
+    return mix_steps(color, Color::new(0.0, 0.0, 0.0, color.a), count);
+}
+
+/// Generate tones of a color, mixing it with increasing amounts of a gray of the same perceived lightness.
+///
+/// Unlike mixing with a fixed gray, this only lowers the chroma, so all tones stay as light as the base color.
+///
+/// ## Returns
+/// A `Vec` of `count` colors, starting with the base color and evenly approaching, but not reaching, gray. See [`tints`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{tones, Color};
+///
+/// let steps = tones(Color::from_rgb8(255, 128, 0), 3);
+/// assert!(steps[2].to_oklch()[1] < steps[1].to_oklch()[1]);
+/// assert!((steps[2].to_oklch()[0] - steps[0].to_oklch()[0]).abs() < 0.001);
+/// ```
+pub fn tones(color: Color, count: usize) -> Vec<Color> {
+    let lightness = color.to_oklab()[0];
+    return mix_steps(color, Color::from_oklab(lightness, 0.0, 0.0, color.a), count);
+}
+
+/// Generate a ramp of evenly spaced colors between two colors, interpolated in `OKLab`.
+///
+/// ## Parameters
+/// - `from`: the first color
+/// - `to`: the last color
+/// - `count`: the amount of colors to generate, including both ends
+///
+/// ## Returns
+/// A `Vec` of `count` colors from `from` to `to`, both included. A `count` of `1` only returns `from`. Alpha is interpolated too.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{ramp, Color};
+///
+/// let steps = ramp(Color::from_rgb8(0, 0, 0), Color::from_rgb8(255, 255, 255), 3);
+/// assert_eq!(steps[0].to_hex(), "#000000");
+/// assert_eq!(steps[1].to_hex(), "#636363"); // perceptually halfway, not #808080
+/// assert_eq!(steps[2].to_hex(), "#ffffff");
+/// ```
+pub fn ramp(from: Color, to: Color, count: usize) -> Vec<Color> {
+    if count == 1 {
+        return vec![from];
+    }
+    return (0..count).map(|i| mix_oklab(from, to, i as f32 / (count - 1) as f32)).collect();
+}
+
+/// Generate a ramp of a color at evenly spaced perceived lightness, from dark to light, keeping its hue and chroma.
+///
+/// This is useful for UI color scales, e.g. the `50` to `950` steps of a design system.
+///
+/// ## Parameters
+/// - `color`: the base color, whose hue and chroma are used
+/// - `count`: the amount of colors to generate
+///
+/// ## Returns
+/// A `Vec` of `count` colors, with `OKLCH` lightness evenly spaced strictly between `0.0` and `1.0`, so neither black nor white are included.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{lightness_ramp, Color};
+///
+/// let steps = lightness_ramp(Color::from_rgb8(58, 31, 156), 9);
+/// assert_eq!(steps.len(), 9);
+/// assert!((steps[4].to_oklch()[0] - 0.5).abs() < 0.001);
+/// ```
+pub fn lightness_ramp(color: Color, count: usize) -> Vec<Color> {
+    let [_, c, h] = color.to_oklch();
+    return (1..=count)
+        .map(|i| Color::from_oklch(i as f32 / (count + 1) as f32, c, h, color.a))
+        .collect();
+}
+
+/// Mixes `count` evenly spaced steps from `from` towards `to`, starting at `from` and excluding `to`.
+fn mix_steps(from: Color, to: Color, count: usize) -> Vec<Color> {
+    return (0..count).map(|i| mix_oklab(from, to, i as f32 / count as f32)).collect();
+}
+
+/// Mixes two colors in `OKLab`, where `t` of `0.0` is `from` and `1.0` is `to`.
+pub(super) fn mix_oklab(from: Color, to: Color, t: f32) -> Color {
+    let a = from.to_oklab();
+    let b = to.to_oklab();
+    let lab: [f32; 3] = std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t);
+    return Color::from_oklab(lab[0], lab[1], lab[2], from.a + (to.a - from.a) * t);
+}
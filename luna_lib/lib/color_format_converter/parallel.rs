@@ -0,0 +1,85 @@
+//! Multi-threaded conversion of large buffers, e.g. whole video frames.
+
+use std::thread;
+
+use super::{check_input, convert_row, swizzle_map, ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// The fewest pixels worth a thread of their own, below this starting threads costs more than it saves.
+const MIN_PIXELS_PER_THREAD: usize = 1 << 16;
+
+/// Convert raw pixel channel values from one color model to another, splitting the buffer across several threads.
+///
+/// Works exactly like [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic), with the same result,
+/// but large buffers are converted in parallel, one slice of pixels per thread. Buffers of less than `65536` pixels
+/// per thread use fewer threads, so small buffers are converted on the calling thread.
+///
+/// ## Parameters
+/// - `data`: input channel values, length must be a multiple of `from.bytes_per_pixel()`
+/// - `from`: source color model (e.g. `ColorFormat::RGBA`)
+/// - `to`: destination color model (e.g. `ColorFormat::BGR`)
+/// - `threads`: the most threads to use, or `0` for as many as the system has cores
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<T>`, or any of the errors of
+/// [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_vec_color_model, convert_vec_color_model_parallel, ColorFormat};
+///
+/// // A 1920x1080 RGBA frame
+/// let rgba: Vec<u8> = (0..1920 * 1080 * 4).map(|i| (i % 251) as u8).collect();
+///
+/// let bgr = convert_vec_color_model_parallel(&rgba, ColorFormat::RGBA, ColorFormat::BGR, 0).unwrap();
+/// assert_eq!(bgr, convert_vec_color_model(&rgba, ColorFormat::RGBA, ColorFormat::BGR).unwrap());
+/// ```
+pub fn convert_vec_color_model_parallel<T: ChannelType + Send + Sync>(
+    data: &[T],
+    from: ColorFormat,
+    to: ColorFormat,
+    threads: usize
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    check_input(data, from, to)?;
+
+    let in_bytes = from.bytes_per_pixel();
+    let out_bytes = to.bytes_per_pixel();
+    let pixel_count = data.len() / in_bytes;
+
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let threads = threads.min(pixel_count.div_ceil(MIN_PIXELS_PER_THREAD));
+    if threads <= 1 {
+        let mut out = Vec::with_capacity(pixel_count * out_bytes);
+        convert_row(data, from, to, &mut out)?;
+        return Ok(out);
+    }
+
+    // a plain channel reordering is written straight into the output, everything else is converted into a buffer first
+    let swizzle = swizzle_map::<T>(from, to);
+
+    let per_thread = pixel_count.div_ceil(threads);
+    let mut out = vec![T::MAX; pixel_count * out_bytes];
+
+    let results: Vec<Result<(), ColorFormatConverterError>> = thread::scope(|scope| {
+        let handles: Vec<_> = data.chunks(per_thread * in_bytes)
+            .zip(out.chunks_mut(per_thread * out_bytes))
+            .map(|(input, output)| scope.spawn(move || {
+                if let Some(map) = swizzle {
+                    map.apply_into(input, output);
+                    return Ok(());
+                }
+                let mut converted = Vec::with_capacity(output.len());
+                convert_row(input, from, to, &mut converted)?;
+                output.copy_from_slice(&converted);
+                return Ok(());
+            }))
+            .collect();
+        return handles.into_iter().map(|handle| handle.join().expect("a conversion thread panicked")).collect();
+    });
+
+    results.into_iter().collect::<Result<(), _>>()?;
+    return Ok(out);
+}
@@ -0,0 +1,400 @@
+//! Color quantization: reducing a buffer to a small palette and an indexed buffer, e.g. for `GIF` or `PNG8` export,
+//! and extracting the dominant colors of an image.
+
+use std::collections::HashMap;
+
+use super::{convert_vec_color_model, Color, ColorFormat, ColorFormatConverterError};
+
+/// The algorithms available for building a palette.
+///
+/// - `MedianCut`: Repeatedly splits the box of colors with the widest range at its median. Fast, and good for photos.
+/// - `Octree`: Builds a tree of colors by their bits, and merges the least used branches. Fast, and keeps rare but distinct colors.
+/// - `KMeans`: Refines a median cut palette with k-means clustering in `OKLab`. Slowest, but gives the most perceptually accurate palette.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum QuantizeMethod {
+    MedianCut,
+    Octree,
+    KMeans,
+}
+
+/// The result of quantizing a buffer: a palette, and the index into it of every pixel.
+#[derive(PartialEq, Debug, Clone)]
+pub struct QuantizedBuffer {
+    /// The colors of the palette, at most as many as requested, and only those that are used.
+    /// The alpha of every color is the average alpha of the pixels it replaces.
+    pub palette: Vec<Color>,
+    /// The index into `palette` of every pixel of the input.
+    pub indices: Vec<u8>,
+}
+
+impl QuantizedBuffer {
+    /// Returns the quantized pixels as an `RGBA` buffer, with every index replaced by its palette color.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let palette: Vec<[u8; 4]> = self.palette.iter().map(Color::to_rgba8).collect();
+        return self.indices.iter().flat_map(|&i| palette[i as usize]).collect();
+    }
+}
+
+/// The most colors a palette can have, so that indices fit into a byte.
+pub const MAX_PALETTE_SIZE: usize = 256;
+
+/// Maximum amount of k-means iterations, which usually converge much sooner.
+const KMEANS_ITERATIONS: usize = 24;
+
+/// Quantize a buffer of 8-bit pixels into a palette of at most `max_colors` colors, and an indexed buffer.
+///
+/// Colors are quantized by their `RGB` channels only, so pixels that only differ in alpha share a palette color.
+/// Every pixel is mapped to the perceptually nearest palette color, in `OKLab`.
+///
+/// ## Parameters
+/// - `data`: input byte-slice, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`, converted to `RGBA` first if it is another format
+/// - `max_colors`: the most colors the palette may have, from `1` to [`MAX_PALETTE_SIZE`]
+/// - `method`: the algorithm to build the palette with
+///
+/// ## Returns
+/// A `Result` containing either the [`QuantizedBuffer`], or a [`ColorFormatConverterError`]: `OutOfRange` for an invalid `max_colors`,
+/// or any of the errors of [`convert_vec_color_model`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{quantize, ColorFormat, QuantizeMethod};
+///
+/// // Two reds and two blues
+/// let rgba = vec![250, 0, 0, 255,  240, 10, 0, 255,  0, 0, 250, 255,  10, 0, 240, 255];
+/// let quantized = quantize(&rgba, ColorFormat::RGBA, 2, QuantizeMethod::MedianCut).unwrap();
+///
+/// assert_eq!(quantized.palette.len(), 2);
+/// assert_eq!(quantized.indices[0], quantized.indices[1]);
+/// assert_ne!(quantized.indices[1], quantized.indices[2]);
+/// assert_eq!(quantized.palette[quantized.indices[0] as usize].to_rgb8(), [245, 5, 0]);
+///
+/// // A buffer with few enough colors is kept as is
+/// let exact = quantize(&rgba, ColorFormat::RGBA, 4, QuantizeMethod::Octree).unwrap();
+/// assert_eq!(exact.to_rgba8(), rgba);
+/// ```
+pub fn quantize(data: &[u8], format: ColorFormat, max_colors: usize, method: QuantizeMethod) -> Result<QuantizedBuffer, ColorFormatConverterError> {
+    if !(1..=MAX_PALETTE_SIZE).contains(&max_colors) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    let rgba = to_rgba(data, format)?;
+    let histogram = histogram(&rgba);
+
+    let palette: Vec<[f32; 3]> = if histogram.len() <= max_colors {
+        histogram.iter().map(|(c, _)| c.map(|v| v as f32 / 255.0)).collect()
+    } else {
+        match method {
+            QuantizeMethod::MedianCut => median_cut(&histogram, max_colors),
+            QuantizeMethod::Octree => octree(&histogram, max_colors),
+            QuantizeMethod::KMeans => kmeans(&histogram, &median_cut(&histogram, max_colors)),
+        }
+    };
+
+    return Ok(index(&rgba, &histogram, &palette));
+}
+
+/// Extract the dominant colors of a buffer of 8-bit pixels, with how much of the buffer each covers.
+///
+/// The colors are found with [`QuantizeMethod::KMeans`].
+///
+/// ## Parameters
+/// - `data`: input byte-slice, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`
+/// - `count`: the most colors to extract, from `1` to [`MAX_PALETTE_SIZE`]
+///
+/// ## Returns
+/// A `Result` containing either a `Vec` of `(color, percentage)` pairs, from the most to the least common,
+/// where the percentages add up to `100.0`, or a [`ColorFormatConverterError`] like [`quantize`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{dominant_colors, ColorFormat};
+///
+/// // Three shades of green and one red
+/// let rgb = vec![0, 200, 0,  0, 210, 0,  0, 190, 0,  255, 0, 0];
+/// let dominant = dominant_colors(&rgb, ColorFormat::RGB, 2).unwrap();
+///
+/// assert_eq!(dominant[0].0.to_rgb8(), [0, 200, 0]);
+/// assert_eq!(dominant[0].1, 75.0);
+/// assert_eq!(dominant[1].0.to_rgb8(), [255, 0, 0]);
+/// assert_eq!(dominant[1].1, 25.0);
+/// ```
+pub fn dominant_colors(data: &[u8], format: ColorFormat, count: usize) -> Result<Vec<(Color, f32)>, ColorFormatConverterError> {
+    let quantized = quantize(data, format, count, QuantizeMethod::KMeans)?;
+
+    let mut usage = vec![0usize; quantized.palette.len()];
+    for &i in &quantized.indices {
+        usage[i as usize] += 1;
+    }
+    let total = quantized.indices.len() as f32;
+    let mut dominant: Vec<(Color, f32)> = quantized.palette.iter()
+        .zip(usage)
+        .map(|(&color, n)| (color, n as f32 / total * 100.0))
+        .collect();
+    dominant.sort_by(|a, b| b.1.total_cmp(&a.1));
+    return Ok(dominant);
+}
+
+fn to_rgba(data: &[u8], format: ColorFormat) -> Result<Vec<u8>, ColorFormatConverterError> {
+    if format != ColorFormat::RGBA {
+        return convert_vec_color_model(data, format, ColorFormat::RGBA);
+    }
+    if data.is_empty() || !data.len().is_multiple_of(4) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    return Ok(data.to_vec());
+}
+
+/// The unique `RGB` colors of an `RGBA` buffer with their pixel counts, sorted by color so results are deterministic.
+fn histogram(rgba: &[u8]) -> Vec<([u8; 3], u32)> {
+    let mut counts: HashMap<[u8; 3], u32> = HashMap::new();
+    for pixel in rgba.chunks_exact(4) {
+        *counts.entry([pixel[0], pixel[1], pixel[2]]).or_insert(0) += 1;
+    }
+    let mut histogram: Vec<([u8; 3], u32)> = counts.into_iter().collect();
+    histogram.sort_unstable();
+    return histogram;
+}
+
+/// The count-weighted mean of colors, normalized.
+fn mean(colors: &[([u8; 3], u32)]) -> [f32; 3] {
+    let mut sum = [0u64; 3];
+    let mut total = 0u64;
+    for (color, n) in colors {
+        for (s, &c) in sum.iter_mut().zip(color) {
+            *s += c as u64 * *n as u64;
+        }
+        total += *n as u64;
+    }
+    return sum.map(|s| s as f32 / total as f32 / 255.0);
+}
+
+fn median_cut(histogram: &[([u8; 3], u32)], max_colors: usize) -> Vec<[f32; 3]> {
+    let mut boxes: Vec<Vec<([u8; 3], u32)>> = vec![histogram.to_vec()];
+
+    while boxes.len() < max_colors {
+        // the box with the widest channel range, and that channel
+        let widest = boxes.iter().enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let ranges: [u8; 3] = std::array::from_fn(|ch| {
+                    let (min, max) = b.iter().fold((255, 0), |(min, max), (c, _)| (c[ch].min(min), c[ch].max(max)));
+                    max - min
+                });
+                let channel = (0..3).max_by_key(|&ch| ranges[ch]).unwrap_or(0);
+                (i, channel, ranges[channel])
+            })
+            .max_by_key(|&(_, _, range)| range);
+        let Some((i, channel, _)) = widest else { break };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_by_key(|(c, _)| c[channel]);
+
+        // split at the weighted median, keeping both halves non-empty
+        let total: u64 = colors.iter().map(|(_, n)| *n as u64).sum();
+        let mut seen = 0u64;
+        let mut split = colors.len() - 1;
+        for (j, (_, n)) in colors.iter().enumerate() {
+            seen += *n as u64;
+            if seen * 2 >= total {
+                split = (j + 1).clamp(1, colors.len() - 1);
+                break;
+            }
+        }
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    return boxes.iter().map(|b| mean(b)).collect();
+}
+
+/// A node of the octree, whose counts and sums include every color below it.
+#[derive(Clone, Default)]
+struct OctreeNode {
+    children: [Option<usize>; 8],
+    count: u64,
+    sum: [u64; 3],
+    leaf: bool,
+}
+
+fn octree(histogram: &[([u8; 3], u32)], max_colors: usize) -> Vec<[f32; 3]> {
+    let mut nodes = vec![OctreeNode::default()];
+    // internal nodes by depth, for reducing the deepest first
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); 8];
+    let mut leaves = 0usize;
+
+    for &(color, n) in histogram {
+        let mut node = 0;
+        for (depth, level) in levels.iter_mut().enumerate() {
+            add_color(&mut nodes[node], color, n);
+
+            let shift = 7 - depth;
+            let child = (((color[0] >> shift) & 1) << 2 | ((color[1] >> shift) & 1) << 1 | ((color[2] >> shift) & 1)) as usize;
+            node = match nodes[node].children[child] {
+                Some(existing) => existing,
+                None => {
+                    if nodes[node].children.iter().all(Option::is_none) {
+                        level.push(node);
+                    }
+                    nodes.push(OctreeNode::default());
+                    let created = nodes.len() - 1;
+                    nodes[node].children[child] = Some(created);
+                    created
+                },
+            };
+        }
+
+        // the full 8 bits of the color
+        add_color(&mut nodes[node], color, n);
+        if !nodes[node].leaf {
+            nodes[node].leaf = true;
+            leaves += 1;
+        }
+    }
+
+    // merge the least used nodes of the deepest level into leaves, until there are few enough,
+    // skipping nodes that would leave fewer leaves than requested
+    for level in levels.iter_mut().rev() {
+        level.sort_by_key(|&node| nodes[node].count);
+        for &node in level.iter() {
+            if leaves <= max_colors {
+                break;
+            }
+            let merged = leaf_count(&nodes, node);
+            if leaves + 1 - merged >= max_colors {
+                nodes[node].leaf = true;
+                leaves = leaves + 1 - merged;
+            }
+        }
+    }
+
+    let mut remaining: Vec<(u64, [u64; 3])> = Vec::with_capacity(leaves);
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        let n = &nodes[node];
+        if n.leaf {
+            remaining.push((n.count, n.sum));
+        } else {
+            stack.extend(n.children.iter().flatten());
+        }
+    }
+
+    // the skipped nodes can leave a few too many, so merge the least used leaves into their nearest ones
+    let average = |(count, sum): (u64, [u64; 3])| sum.map(|s| s as f32 / count as f32 / 255.0);
+    while remaining.len() > max_colors {
+        let least = (0..remaining.len()).min_by_key(|&i| remaining[i].0).unwrap_or(0);
+        let (count, sum) = remaining.swap_remove(least);
+        let target = nearest(&remaining.iter().map(|&leaf| average(leaf)).collect::<Vec<_>>(), &average((count, sum)));
+        remaining[target].0 += count;
+        for (s, v) in remaining[target].1.iter_mut().zip(sum) {
+            *s += v;
+        }
+    }
+    return remaining.into_iter().map(average).collect();
+}
+
+fn add_color(node: &mut OctreeNode, color: [u8; 3], n: u32) {
+    node.count += n as u64;
+    for (s, &c) in node.sum.iter_mut().zip(&color) {
+        *s += c as u64 * n as u64;
+    }
+}
+
+/// The amount of leaves in the subtree of a node.
+fn leaf_count(nodes: &[OctreeNode], node: usize) -> usize {
+    if nodes[node].leaf {
+        return 1;
+    }
+    return nodes[node].children.iter().flatten().map(|&child| leaf_count(nodes, child)).sum();
+}
+
+fn kmeans(histogram: &[([u8; 3], u32)], initial: &[[f32; 3]]) -> Vec<[f32; 3]> {
+    // bin the colors to 5 bits per channel, so large images stay fast
+    let mut bins: HashMap<[u8; 3], ([f32; 3], f32)> = HashMap::new();
+    for &(color, n) in histogram {
+        let lab = Color::from_rgb8(color[0], color[1], color[2]).to_oklab();
+        let bin = bins.entry(color.map(|c| c >> 3)).or_insert(([0.0; 3], 0.0));
+        for (s, v) in bin.0.iter_mut().zip(lab) {
+            *s += v * n as f32;
+        }
+        bin.1 += n as f32;
+    }
+    let mut points: Vec<([u8; 3], [f32; 3], f32)> = bins.into_iter()
+        .map(|(key, (sum, weight))| (key, sum.map(|s| s / weight), weight))
+        .collect();
+    points.sort_unstable_by_key(|(key, _, _)| *key);
+
+    let mut centroids: Vec<[f32; 3]> = initial.iter()
+        .map(|&[r, g, b]| Color::new(r, g, b, 1.0).to_oklab())
+        .collect();
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![([0.0f32; 3], 0.0f32); centroids.len()];
+        for (_, lab, weight) in &points {
+            let (sum, total) = &mut sums[nearest(&centroids, lab)];
+            for (s, v) in sum.iter_mut().zip(lab) {
+                *s += v * weight;
+            }
+            *total += weight;
+        }
+
+        let mut moved = 0.0f32;
+        for (centroid, (sum, total)) in centroids.iter_mut().zip(sums) {
+            if total > 0.0 {
+                let updated = sum.map(|s| s / total);
+                moved = moved.max(distance(centroid, &updated));
+                *centroid = updated;
+            }
+        }
+        if moved < 1e-4 {
+            break;
+        }
+    }
+
+    return centroids.iter().map(|&[l, a, b]| {
+        let color = Color::from_oklab(l, a, b, 1.0);
+        [color.r, color.g, color.b].map(|c| c.clamp(0.0, 1.0))
+    }).collect();
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    return (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2);
+}
+
+fn nearest(palette: &[[f32; 3]], lab: &[f32; 3]) -> usize {
+    let mut best = (0, f32::INFINITY);
+    for (i, entry) in palette.iter().enumerate() {
+        let d = distance(entry, lab);
+        if d < best.1 {
+            best = (i, d);
+        }
+    }
+    return best.0;
+}
+
+/// Maps every pixel to its nearest palette color in `OKLab`, and drops the palette colors no pixel uses.
+fn index(rgba: &[u8], histogram: &[([u8; 3], u32)], palette: &[[f32; 3]]) -> QuantizedBuffer {
+    let palette_lab: Vec<[f32; 3]> = palette.iter().map(|&[r, g, b]| Color::new(r, g, b, 1.0).to_oklab()).collect();
+    let lookup: HashMap<[u8; 3], usize> = histogram.iter()
+        .map(|&(c, _)| (c, nearest(&palette_lab, &Color::from_rgb8(c[0], c[1], c[2]).to_oklab())))
+        .collect();
+
+    let mut alpha = vec![(0u64, 0u64); palette.len()];
+    let raw: Vec<usize> = rgba.chunks_exact(4).map(|p| {
+        let i = lookup[&[p[0], p[1], p[2]]];
+        alpha[i].0 += p[3] as u64;
+        alpha[i].1 += 1;
+        i
+    }).collect();
+
+    let mut remap = vec![0u8; palette.len()];
+    let mut colors = Vec::with_capacity(palette.len());
+    for (i, &[r, g, b]) in palette.iter().enumerate() {
+        let (alpha_sum, count) = alpha[i];
+        if count > 0 {
+            remap[i] = colors.len() as u8;
+            colors.push(Color::new(r, g, b, alpha_sum as f32 / count as f32 / 255.0));
+        }
+    }
+    return QuantizedBuffer { palette: colors, indices: raw.iter().map(|&i| remap[i]).collect() };
+}
@@ -0,0 +1,248 @@
+//! `CMYK` separation for print: black generation, under color removal (`UCR`), gray component replacement (`GCR`)
+//! and total ink limits.
+//!
+//! Like the rest of the module this uses the naive, device-independent `CMYK` model, where every amount of black from none
+//! to the gray component (the smallest of `C`, `M` and `Y`) reproduces the same color. Separation chooses how much black to use,
+//! which matters on press: more black saves ink and keeps grays neutral, less black gives smoother skin tones and highlights.
+
+use super::{channel_order, check_values, convert_vec_color_model_generic, Channel, ChannelType, ColorFormat, ColorFormatConverterError};
+use super::models::{family, Family};
+
+/// How much black ink replaces the gray component of a color.
+///
+/// - `None`: No black at all, every color is printed with `C`, `M` and `Y`.
+/// - `Light`: Black starts at `50%` gray, e.g. for portraits.
+/// - `Medium`: Black starts at `25%` gray, the usual choice for most images.
+/// - `Heavy`: Black starts right away, growing with the square of the gray component, e.g. for dark images on uncoated paper.
+/// - `Maximum`: All of the gray component is black, as [`from_rgb_to_cmyk`](super::from_rgb_to_cmyk) does.
+///
+/// All curves reach full black at `100%` gray.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BlackGeneration {
+    None,
+    Light,
+    Medium,
+    Heavy,
+    Maximum,
+}
+
+impl BlackGeneration {
+    /// The amount of black for a gray component, both normalized.
+    fn black(&self, gray: f32) -> f32 {
+        let start = match self {
+            BlackGeneration::None => return 0.0,
+            BlackGeneration::Maximum => return gray,
+            BlackGeneration::Light => 0.5,
+            BlackGeneration::Medium => 0.25,
+            BlackGeneration::Heavy => 0.0,
+        };
+        return gray * ((gray - start) / (1.0 - start)).clamp(0.0, 1.0);
+    }
+}
+
+/// Where black replaces `C`, `M` and `Y`.
+///
+/// - `UCR`: Under color removal, black only replaces `CMY` in neutral colors, and fades out with increasing saturation.
+/// - `GCR`: Gray component replacement, black replaces the gray component of every color, including saturated ones.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BlackReplacement {
+    UCR,
+    GCR,
+}
+
+/// The settings of a `CMYK` separation.
+///
+/// The default separation uses maximum black with `GCR` and no ink limit, which is the same as [`from_rgb_to_cmyk`](super::from_rgb_to_cmyk).
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{BlackGeneration, BlackReplacement, SeparationOptions};
+///
+/// // A common setting for sheetfed offset on coated paper
+/// let options = SeparationOptions {
+///     black_generation: BlackGeneration::Medium,
+///     replacement: BlackReplacement::GCR,
+///     total_ink_limit: 300.0,
+/// };
+/// assert_eq!(SeparationOptions::default().total_ink_limit, 400.0);
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SeparationOptions {
+    /// How much black replaces the gray component.
+    pub black_generation: BlackGeneration,
+    /// Which colors black is used in.
+    pub replacement: BlackReplacement,
+    /// The most ink any pixel may have, as the sum of all four inks in percent, from `100.0` to `400.0` (no limit).
+    /// Presses usually allow `240%` to `340%`, depending on the paper.
+    pub total_ink_limit: f32,
+}
+
+impl Default for SeparationOptions {
+    fn default() -> Self {
+        return Self {
+            black_generation: BlackGeneration::Maximum,
+            replacement: BlackReplacement::GCR,
+            total_ink_limit: 400.0,
+        };
+    }
+}
+
+/// Amount of black levels tried when raising black to meet the total ink limit.
+const INK_LIMIT_STEPS: usize = 32;
+
+/// Convert raw pixel bytes from `RGB` color model to `CMYK`, with the given black generation and ink limit.
+///
+/// If a color needs more ink than the limit allows, black first replaces more of the gray component, which keeps the color.
+/// If the limit is still exceeded, `C`, `M` and `Y` are reduced evenly, which makes the color lighter.
+///
+/// ## Parameters
+/// - `r`: Red channel value `(0-255)`
+/// - `g`: Green channel value `(0-255)`
+/// - `b`: Blue channel value `(0-255)`
+/// - `options`: the separation settings
+///
+/// ## Returns
+/// A `Result` containing either a tuple of `f32`'s with the `CMYK` channel values in the range `0-255`, like [`from_rgb_to_cmyk`](super::from_rgb_to_cmyk),
+/// or `OutOfRange` (or `NotANumber`) for an invalid `total_ink_limit`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{from_rgb_to_cmyk, from_rgb_to_cmyk_separated, BlackGeneration, BlackReplacement, SeparationOptions};
+///
+/// // The default separation is the same as `from_rgb_to_cmyk`
+/// let separated = from_rgb_to_cmyk_separated(58, 31, 156, &SeparationOptions::default()).unwrap();
+/// assert_eq!(separated, from_rgb_to_cmyk(58, 31, 156));
+///
+/// // Without black, gray is printed with C, M and Y only
+/// let options = SeparationOptions { black_generation: BlackGeneration::None, ..Default::default() };
+/// assert_eq!(from_rgb_to_cmyk_separated(128, 128, 128, &options).unwrap(), (127.0, 127.0, 127.0, 0.0));
+///
+/// // A dark brown needs 286% ink, so a 260% limit adds black instead
+/// let options = SeparationOptions { total_ink_limit: 260.0, ..options };
+/// let (c, m, y, k) = from_rgb_to_cmyk_separated(20, 10, 5, &options).unwrap();
+/// assert!(k > 0.0);
+/// assert!(c + m + y + k <= 2.6 * 255.0 + 2.0);
+/// ```
+pub fn from_rgb_to_cmyk_separated(r: u8, g: u8, b: u8, options: &SeparationOptions) -> Result<(f32, f32, f32, f32), ColorFormatConverterError> {
+    check_options(options)?;
+    let [c, m, y, k] = separate([r, g, b].map(|v| v as f32 / 255.0), options);
+    return Ok(((c * 255.0).round(), (m * 255.0).round(), (y * 255.0).round(), (k * 255.0).round()));
+}
+
+/// Convert a buffer of pixels of any format to a `CMYK` format, with the given black generation and ink limit.
+/// See [`from_rgb_to_cmyk_separated`].
+///
+/// ## Parameters
+/// - `data`: input slice of pixels, length must be a multiple of `from.bytes_per_pixel()`
+/// - `from`: color model of `data`
+/// - `to`: a `CMYK` or `CMYKA` format, in any channel order
+/// - `options`: the separation settings
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the `to` format, or a [`ColorFormatConverterError`]: `UnsupportedFormat` if `to`
+/// isn't a `CMYK` format, `OutOfRange` (or `NotANumber`) for an invalid `total_ink_limit`,
+/// or any of the errors of [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{convert_to_cmyk_separated, BlackReplacement, ColorFormat, SeparationOptions};
+///
+/// let options = SeparationOptions { replacement: BlackReplacement::UCR, ..Default::default() };
+/// let rgb: Vec<u8> = vec![128, 128, 128,  255, 0, 0];
+/// let cmyk = convert_to_cmyk_separated(&rgb, ColorFormat::RGB, ColorFormat::CMYK, &options).unwrap();
+///
+/// // Neutral gray is all black, saturated red has none
+/// assert_eq!(cmyk, vec![0, 0, 0, 127,  0, 255, 255, 0]);
+/// ```
+pub fn convert_to_cmyk_separated<T: ChannelType>(
+    data: &[T],
+    from: ColorFormat,
+    to: ColorFormat,
+    options: &SeparationOptions
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    if family(to) != Family::Cmyk {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    check_options(options)?;
+    let rgba = if from != ColorFormat::RGBA {
+        convert_vec_color_model_generic(data, from, ColorFormat::RGBA)?
+    } else {
+        if data.is_empty() || !data.len().is_multiple_of(4) {
+            return Err(ColorFormatConverterError::InvalidInputLength);
+        }
+        check_values(data, from)?;
+        data.to_vec()
+    };
+
+    let order = channel_order(to);
+    let mut out = Vec::with_capacity(rgba.len() / 4 * order.len());
+    for pixel in rgba.chunks_exact(4) {
+        let [c, m, y, k] = separate([pixel[0], pixel[1], pixel[2]].map(|v| v.to_normalized()), options);
+        for ch in order {
+            let value = match ch {
+                Channel::C => c,
+                Channel::M => m,
+                Channel::Y => y,
+                Channel::K => k,
+                _ => pixel[3].to_normalized(),
+            };
+            out.push(T::from_normalized(value));
+        }
+    }
+    return Ok(out);
+}
+
+fn check_options(options: &SeparationOptions) -> Result<(), ColorFormatConverterError> {
+    if options.total_ink_limit.is_nan() {
+        return Err(ColorFormatConverterError::NotANumber);
+    }
+    if !(100.0..=400.0).contains(&options.total_ink_limit) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    return Ok(());
+}
+
+/// Separates normalized `sRGB` into normalized `CMYK`.
+fn separate(rgb: [f32; 3], options: &SeparationOptions) -> [f32; 4] {
+    let cmy = rgb.map(|v| 1.0 - v.clamp(0.0, 1.0));
+    let gray = cmy[0].min(cmy[1]).min(cmy[2]);
+    let chroma = cmy[0].max(cmy[1]).max(cmy[2]) - gray;
+
+    let mut black = options.black_generation.black(gray);
+    if options.replacement == BlackReplacement::UCR {
+        black *= 1.0 - chroma;
+    }
+
+    let limit = options.total_ink_limit / 100.0;
+    let total = |inks: &[f32; 4]| inks.iter().sum::<f32>();
+    let mut inks = replace_gray(cmy, black);
+    if total(&inks) <= limit {
+        return inks;
+    }
+
+    // raise black towards all of the gray component, which keeps the color
+    for step in 1..=INK_LIMIT_STEPS {
+        let candidate = replace_gray(cmy, black + (gray - black) * step as f32 / INK_LIMIT_STEPS as f32);
+        if total(&candidate) < total(&inks) {
+            inks = candidate;
+        }
+        if total(&inks) <= limit {
+            return inks;
+        }
+    }
+
+    // still too much, so reduce the colored inks
+    let [c, m, y, k] = inks;
+    let scale = ((limit - k) / (c + m + y)).clamp(0.0, 1.0);
+    return [c * scale, m * scale, y * scale, k];
+}
+
+/// Replaces `black` of the gray component of `cmy` with black ink, keeping the color.
+fn replace_gray(cmy: [f32; 3], black: f32) -> [f32; 4] {
+    if black >= 1.0 {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+    let [c, m, y] = cmy.map(|v| ((v - black) / (1.0 - black)).max(0.0));
+    return [c, m, y, black];
+}
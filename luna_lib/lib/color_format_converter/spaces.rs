@@ -0,0 +1,399 @@
+//! Device-independent color spaces: `CIE XYZ`, `CIE L*a*b*`, `LCh(ab)`, `OKLab` and `OKLCH`.
+//!
+//! All of them are reached from linear `sRGB`, using the `sRGB` primaries with a `D65` white point.
+
+use super::TransferFunction;
+
+/// A reference white, the color that is considered "white" by a color space.
+///
+/// - `D65`: Noon daylight, the white point of `sRGB` and most displays.
+/// - `D50`: Horizon daylight, the white point of print (`ICC` profiles) and of `CIE L*a*b*` in this module.
+///
+/// Colors are moved between white points with the Bradford chromatic adaptation transform.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum WhitePoint {
+    D65,
+    D50,
+}
+
+impl WhitePoint {
+    /// The `XYZ` coordinates of the white point, normalized to `Y = 1.0`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::WhitePoint;
+    ///
+    /// assert_eq!(WhitePoint::D65.xyz(), (0.95047, 1.0, 1.08883));
+    /// ```
+    pub fn xyz(&self) -> (f32, f32, f32) {
+        return match self {
+            WhitePoint::D65 => (0.95047, 1.0, 1.08883),
+            WhitePoint::D50 => (0.96422, 1.0, 0.82521),
+        };
+    }
+}
+
+pub(super) type Triplet = [f32; 3];
+
+pub(super) fn mul(m: &[[f32; 3]; 3], v: Triplet) -> Triplet {
+    return [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ];
+}
+
+const SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.119192,  0.9503041],
+];
+
+const XYZ_TO_SRGB: [[f32; 3]; 3] = [
+    [ 3.2404542, -1.5371385, -0.4985314],
+    [-0.969266,   1.8760108,  0.0415560],
+    [ 0.0556434, -0.2040259,  1.0572252],
+];
+
+const BRADFORD: [[f32; 3]; 3] = [
+    [ 0.8951,  0.2664, -0.1614],
+    [-0.7502,  1.7135,  0.0367],
+    [ 0.0389, -0.0685,  1.0296],
+];
+
+const BRADFORD_INVERSE: [[f32; 3]; 3] = [
+    [ 0.9869929, -0.1470543, 0.1599627],
+    [ 0.4323053,  0.5183603, 0.0492912],
+    [-0.0085287,  0.0400428, 0.9684867],
+];
+
+/// Linear `sRGB` to `XYZ` relative to `D65`.
+pub(super) fn linear_srgb_to_xyz(rgb: Triplet) -> Triplet {
+    return mul(&SRGB_TO_XYZ, rgb);
+}
+
+/// `XYZ` relative to `D65` to linear `sRGB`.
+pub(super) fn xyz_to_linear_srgb(xyz: Triplet) -> Triplet {
+    return mul(&XYZ_TO_SRGB, xyz);
+}
+
+/// Moves `XYZ` coordinates from one white point to another, with the Bradford transform.
+pub(super) fn adapt(xyz: Triplet, from: WhitePoint, to: WhitePoint) -> Triplet {
+    if from == to { return xyz; }
+
+    let (fx, fy, fz) = from.xyz();
+    let (tx, ty, tz) = to.xyz();
+    return adapt_xyz(xyz, [fx, fy, fz], [tx, ty, tz]);
+}
+
+/// Moves `XYZ` coordinates from one white, given as `XYZ`, to another, with the Bradford transform.
+pub(super) fn adapt_xyz(xyz: Triplet, from: Triplet, to: Triplet) -> Triplet {
+    let source = mul(&BRADFORD, from);
+    let target = mul(&BRADFORD, to);
+
+    let cone = mul(&BRADFORD, xyz);
+    let scaled = [cone[0] * target[0] / source[0], cone[1] * target[1] / source[1], cone[2] * target[2] / source[2]];
+    return mul(&BRADFORD_INVERSE, scaled);
+}
+
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+
+/// `XYZ` to `CIE L*a*b*`, both relative to the same white point.
+pub(super) fn xyz_to_lab(xyz: Triplet, white: WhitePoint) -> Triplet {
+    let (wx, wy, wz) = white.xyz();
+    let f = |t: f32| if t > LAB_EPSILON { t.cbrt() } else { (LAB_KAPPA * t + 16.0) / 116.0 };
+
+    let fx = f(xyz[0] / wx);
+    let fy = f(xyz[1] / wy);
+    let fz = f(xyz[2] / wz);
+
+    return [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)];
+}
+
+/// `CIE L*a*b*` to `XYZ`, both relative to the same white point.
+pub(super) fn lab_to_xyz(lab: Triplet, white: WhitePoint) -> Triplet {
+    let (wx, wy, wz) = white.xyz();
+
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+
+    let finv = |f: f32| if f.powi(3) > LAB_EPSILON { f.powi(3) } else { (116.0 * f - 16.0) / LAB_KAPPA };
+    let y = if lab[0] > LAB_KAPPA * LAB_EPSILON { fy.powi(3) } else { lab[0] / LAB_KAPPA };
+
+    return [finv(fx) * wx, y * wy, finv(fz) * wz];
+}
+
+/// Rectangular `a`/`b` coordinates to polar chroma and hue in degrees `(0-360)`.
+pub(super) fn to_polar(lab: Triplet) -> Triplet {
+    let c = (lab[1] * lab[1] + lab[2] * lab[2]).sqrt();
+    let h = lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0);
+    return [lab[0], c, h];
+}
+
+/// Polar chroma and hue in degrees to rectangular `a`/`b` coordinates.
+pub(super) fn from_polar(lch: Triplet) -> Triplet {
+    let h = lch[2].to_radians();
+    return [lch[0], lch[1] * h.cos(), lch[1] * h.sin()];
+}
+
+/// Linear `sRGB` to `OKLab`.
+pub(super) fn linear_srgb_to_oklab(rgb: Triplet) -> Triplet {
+    let [r, g, b] = rgb;
+    let l = (0.41222147 * r + 0.53633253 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    return [
+        0.21045425 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277176 * m - 0.80867576 * s,
+    ];
+}
+
+/// `OKLab` to linear `sRGB`.
+pub(super) fn oklab_to_linear_srgb(lab: Triplet) -> Triplet {
+    let [l, a, b] = lab;
+    let l_ = (l + 0.39633777 * a + 0.21580375 * b).powi(3);
+    let m_ = (l - 0.105561345 * a - 0.06385417 * b).powi(3);
+    let s_ = (l - 0.08948418 * a - 1.2914855 * b).powi(3);
+
+    return [
+         4.0767416 * l_ - 3.3077115 * m_ + 0.23096993 * s_,
+        -1.268438 * l_ + 2.6097574 * m_ - 0.34131939 * s_,
+        -0.0041960863 * l_ - 0.7034186 * m_ + 1.7076147 * s_,
+    ];
+}
+
+/// Normalized, `sRGB` encoded `RGB` to linear `sRGB`.
+pub(super) fn srgb_to_linear(rgb: Triplet) -> Triplet {
+    return rgb.map(|c| TransferFunction::SRGB.decode(c));
+}
+
+/// Linear `sRGB` to normalized, `sRGB` encoded `RGB`.
+pub(super) fn linear_to_srgb(rgb: Triplet) -> Triplet {
+    return rgb.map(|c| TransferFunction::SRGB.encode(c));
+}
+
+/// Normalized, `sRGB` encoded `RGB` to `CIE L*a*b*` relative to `D50`.
+pub(super) fn srgb_to_lab(rgb: Triplet) -> Triplet {
+    let xyz = adapt(linear_srgb_to_xyz(srgb_to_linear(rgb)), WhitePoint::D65, WhitePoint::D50);
+    return xyz_to_lab(xyz, WhitePoint::D50);
+}
+
+/// `CIE L*a*b*` relative to `D50` to normalized, `sRGB` encoded `RGB`.
+pub(super) fn lab_to_srgb(lab: Triplet) -> Triplet {
+    let xyz = adapt(lab_to_xyz(lab, WhitePoint::D50), WhitePoint::D50, WhitePoint::D65);
+    return linear_to_srgb(xyz_to_linear_srgb(xyz));
+}
+
+/// Normalized, `sRGB` encoded `RGB` to `OKLab`.
+pub(super) fn srgb_to_oklab(rgb: Triplet) -> Triplet {
+    return linear_srgb_to_oklab(srgb_to_linear(rgb));
+}
+
+/// `OKLab` to normalized, `sRGB` encoded `RGB`.
+pub(super) fn oklab_to_srgb(lab: Triplet) -> Triplet {
+    return linear_to_srgb(oklab_to_linear_srgb(lab));
+}
+
+fn normalize(r: u8, g: u8, b: u8) -> Triplet {
+    return [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0];
+}
+
+fn denormalize(rgb: Triplet) -> (f32, f32, f32) {
+    return (rgb[0] * 255.0, rgb[1] * 255.0, rgb[2] * 255.0);
+}
+
+/// Convert an `sRGB` color to `CIE XYZ`.
+///
+/// ## Parameters
+/// - `r`: Red channel value `(0-255)`
+/// - `g`: Green channel value `(0-255)`
+/// - `b`: Blue channel value `(0-255)`
+/// - `white`: the white point of the result, `D65` is native to `sRGB`, while `D50` is reached through Bradford adaptation
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `X`, `Y` and `Z` values, where `Y` is in the range `0-1`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{from_rgb_to_xyz, WhitePoint};
+///
+/// let (x, y, z) = from_rgb_to_xyz(255, 255, 255, WhitePoint::D65); // White is the white point
+/// assert!((x - 0.95047).abs() < 0.001 && (y - 1.0).abs() < 0.001 && (z - 1.08883).abs() < 0.001);
+///
+/// let (x, y, z) = from_rgb_to_xyz(255, 255, 255, WhitePoint::D50);
+/// assert!((x - 0.96422).abs() < 0.001 && (y - 1.0).abs() < 0.001 && (z - 0.82521).abs() < 0.001);
+/// ```
+pub fn from_rgb_to_xyz(r: u8, g: u8, b: u8, white: WhitePoint) -> (f32, f32, f32) {
+    let xyz = adapt(linear_srgb_to_xyz(srgb_to_linear(normalize(r, g, b))), WhitePoint::D65, white);
+    return (xyz[0], xyz[1], xyz[2]);
+}
+
+/// Convert a `CIE XYZ` color to `sRGB`.
+///
+/// ## Parameters
+/// - `x`, `y`, `z`: the `XYZ` values, with `Y` in the range `0-1`
+/// - `white`: the white point the `XYZ` values are relative to
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `RGB` channel values in the range `0-255`.
+/// Colors outside of the `sRGB` gamut are not clamped, and may be negative or above `255`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{from_xyz_to_rgb, WhitePoint};
+///
+/// let (r, g, b) = from_xyz_to_rgb(0.96422, 1.0, 0.82521, WhitePoint::D50);
+/// assert!((r - 255.0).abs() < 0.5 && (g - 255.0).abs() < 0.5 && (b - 255.0).abs() < 0.5);
+/// ```
+pub fn from_xyz_to_rgb(x: f32, y: f32, z: f32, white: WhitePoint) -> (f32, f32, f32) {
+    let xyz = adapt([x, y, z], white, WhitePoint::D65);
+    return denormalize(linear_to_srgb(xyz_to_linear_srgb(xyz)));
+}
+
+/// Convert an `sRGB` color to `CIE L*a*b*`, relative to a `D50` white point.
+///
+/// ## Parameters
+/// - `r`: Red channel value `(0-255)`
+/// - `g`: Green channel value `(0-255)`
+/// - `b`: Blue channel value `(0-255)`
+///
+/// ## Returns
+/// A tuple of `f32`'s containing `L*` in the range `0-100`, and `a*` and `b*`, which are roughly within `-128-127`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_rgb_to_lab;
+///
+/// let (l, a, b) = from_rgb_to_lab(255, 255, 255); // White
+/// assert!((l - 100.0).abs() < 0.01 && a.abs() < 0.01 && b.abs() < 0.01);
+///
+/// let (l, a, b) = from_rgb_to_lab(255, 0, 0); // Pure red
+/// assert!((l - 54.29).abs() < 0.05 && (a - 80.80).abs() < 0.05 && (b - 69.89).abs() < 0.05);
+/// ```
+pub fn from_rgb_to_lab(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let lab = srgb_to_lab(normalize(r, g, b));
+    return (lab[0], lab[1], lab[2]);
+}
+
+/// Convert a `CIE L*a*b*` color, relative to a `D50` white point, to `sRGB`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `RGB` channel values in the range `0-255`.
+/// Colors outside of the `sRGB` gamut are not clamped, and may be negative or above `255`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_lab_to_rgb;
+///
+/// let (r, g, b) = from_lab_to_rgb(54.29, 80.80, 69.89); // Pure red
+/// assert!((r - 255.0).abs() < 0.5 && g.abs() < 0.5 && b.abs() < 0.5);
+/// ```
+pub fn from_lab_to_rgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    return denormalize(lab_to_srgb([l, a, b]));
+}
+
+/// Convert an `sRGB` color to `CIE LCh(ab)`, the polar form of `CIE L*a*b*`, relative to a `D50` white point.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing lightness `(0-100)`, chroma `(0-~150)` and hue in degrees `(0-360)`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_rgb_to_lch;
+///
+/// let (l, c, h) = from_rgb_to_lch(255, 0, 0); // Pure red
+/// assert!((l - 54.29).abs() < 0.05 && (c - 106.84).abs() < 0.05 && (h - 40.86).abs() < 0.05);
+/// ```
+pub fn from_rgb_to_lch(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let lch = to_polar(srgb_to_lab(normalize(r, g, b)));
+    return (lch[0], lch[1], lch[2]);
+}
+
+/// Convert a `CIE LCh(ab)` color, relative to a `D50` white point, to `sRGB`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `RGB` channel values in the range `0-255`.
+/// Colors outside of the `sRGB` gamut are not clamped, and may be negative or above `255`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_lch_to_rgb;
+///
+/// let (r, g, b) = from_lch_to_rgb(54.29, 106.84, 40.86); // Pure red
+/// assert!((r - 255.0).abs() < 0.5 && g.abs() < 0.5 && b.abs() < 0.5);
+/// ```
+pub fn from_lch_to_rgb(l: f32, c: f32, h: f32) -> (f32, f32, f32) {
+    return denormalize(lab_to_srgb(from_polar([l, c, h])));
+}
+
+/// Convert an `sRGB` color to `OKLab`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing lightness `(0-1)`, and `a` and `b`, which are roughly within `-0.4-0.4`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_rgb_to_oklab;
+///
+/// let (l, a, b) = from_rgb_to_oklab(255, 0, 0); // Pure red
+/// assert!((l - 0.628).abs() < 0.001 && (a - 0.2249).abs() < 0.001 && (b - 0.1258).abs() < 0.001);
+/// ```
+pub fn from_rgb_to_oklab(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let lab = srgb_to_oklab(normalize(r, g, b));
+    return (lab[0], lab[1], lab[2]);
+}
+
+/// Convert an `OKLab` color to `sRGB`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `RGB` channel values in the range `0-255`.
+/// Colors outside of the `sRGB` gamut are not clamped, and may be negative or above `255`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_oklab_to_rgb;
+///
+/// let (r, g, b) = from_oklab_to_rgb(1.0, 0.0, 0.0); // White
+/// assert!((r - 255.0).abs() < 0.5 && (g - 255.0).abs() < 0.5 && (b - 255.0).abs() < 0.5);
+/// ```
+pub fn from_oklab_to_rgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    return denormalize(oklab_to_srgb([l, a, b]));
+}
+
+/// Convert an `sRGB` color to `OKLCH`, the polar form of `OKLab`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing lightness `(0-1)`, chroma `(0-~0.4)` and hue in degrees `(0-360)`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_rgb_to_oklch;
+///
+/// let (l, c, h) = from_rgb_to_oklch(0, 0, 255); // Pure blue
+/// assert!((l - 0.452).abs() < 0.001 && (c - 0.3132).abs() < 0.001 && (h - 264.05).abs() < 0.05);
+/// ```
+pub fn from_rgb_to_oklch(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let lch = to_polar(srgb_to_oklab(normalize(r, g, b)));
+    return (lch[0], lch[1], lch[2]);
+}
+
+/// Convert an `OKLCH` color to `sRGB`.
+///
+/// ## Returns
+/// A tuple of `f32`'s containing the `RGB` channel values in the range `0-255`.
+/// Colors outside of the `sRGB` gamut are not clamped, and may be negative or above `255`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::from_oklch_to_rgb;
+///
+/// let (r, g, b) = from_oklch_to_rgb(0.452, 0.3132, 264.05); // Pure blue
+/// assert!(r.abs() < 0.5 && g.abs() < 0.5 && (b - 255.0).abs() < 0.5);
+/// ```
+pub fn from_oklch_to_rgb(l: f32, c: f32, h: f32) -> (f32, f32, f32) {
+    return denormalize(oklab_to_srgb(from_polar([l, c, h])));
+}
@@ -0,0 +1,349 @@
+//! Spectral colors: light sources and surface reflectances measured as spectra, converted to `CIE XYZ` with the
+//! standard observers, and from there to any other model.
+
+use std::sync::OnceLock;
+
+use super::{spaces, Color, ColorFormatConverterError, WhitePoint};
+
+/// The first wavelength of the built-in tables, in nanometers.
+const FIRST_NM: f32 = 380.0;
+/// The step between two entries of the built-in tables, in nanometers.
+const STEP_NM: f32 = 5.0;
+/// The amount of entries of the built-in tables, from `380nm` to `780nm`.
+const SAMPLES: usize = 81;
+
+/// The `CIE 1931 2°` color matching functions `x̄`, `ȳ` and `z̄`, from `380nm` to `780nm` in `5nm` steps.
+const CIE1931: [[f64; 3]; SAMPLES] = [
+    [0.001368, 0.000039, 0.006450], [0.002236, 0.000064, 0.010550], [0.004243, 0.000120, 0.020050], [0.007650, 0.000217, 0.036210],
+    [0.014310, 0.000396, 0.067850], [0.023190, 0.000640, 0.110200], [0.043510, 0.001210, 0.207400], [0.077630, 0.002180, 0.371300],
+    [0.134380, 0.004000, 0.645600], [0.214770, 0.007300, 1.039050], [0.283900, 0.011600, 1.385600], [0.328500, 0.016840, 1.622960],
+    [0.348280, 0.023000, 1.747060], [0.348060, 0.029800, 1.782600], [0.336200, 0.038000, 1.772110], [0.318700, 0.048000, 1.744100],
+    [0.290800, 0.060000, 1.669200], [0.251100, 0.073900, 1.528100], [0.195360, 0.090980, 1.287640], [0.142100, 0.112600, 1.041900],
+    [0.095640, 0.139020, 0.812950], [0.057950, 0.169300, 0.616200], [0.032010, 0.208020, 0.465180], [0.014700, 0.258600, 0.353300],
+    [0.004900, 0.323000, 0.272000], [0.002400, 0.407300, 0.212300], [0.009300, 0.503000, 0.158200], [0.029100, 0.608200, 0.111700],
+    [0.063270, 0.710000, 0.078250], [0.109600, 0.793200, 0.057250], [0.165500, 0.862000, 0.042160], [0.225750, 0.914850, 0.029840],
+    [0.290400, 0.954000, 0.020300], [0.359700, 0.980300, 0.013400], [0.433450, 0.994950, 0.008750], [0.512050, 1.000000, 0.005750],
+    [0.594500, 0.995000, 0.003900], [0.678400, 0.978600, 0.002750], [0.762100, 0.952000, 0.002100], [0.842500, 0.915400, 0.001800],
+    [0.916300, 0.870000, 0.001650], [0.978600, 0.816300, 0.001400], [1.026300, 0.757000, 0.001100], [1.056700, 0.694900, 0.001000],
+    [1.062200, 0.631000, 0.000800], [1.045600, 0.566800, 0.000600], [1.002600, 0.503000, 0.000340], [0.938400, 0.441200, 0.000240],
+    [0.854450, 0.381000, 0.000190], [0.751400, 0.321000, 0.000100], [0.642400, 0.265000, 0.000050], [0.541900, 0.217000, 0.000030],
+    [0.447900, 0.175000, 0.000020], [0.360800, 0.138200, 0.000010], [0.283500, 0.107000, 0.000000], [0.218700, 0.081600, 0.000000],
+    [0.164900, 0.061000, 0.000000], [0.121200, 0.044580, 0.000000], [0.087400, 0.032000, 0.000000], [0.063600, 0.023200, 0.000000],
+    [0.046770, 0.017000, 0.000000], [0.032900, 0.011920, 0.000000], [0.022700, 0.008210, 0.000000], [0.015840, 0.005723, 0.000000],
+    [0.011359, 0.004102, 0.000000], [0.008111, 0.002929, 0.000000], [0.005790, 0.002091, 0.000000], [0.004109, 0.001484, 0.000000],
+    [0.002899, 0.001047, 0.000000], [0.002049, 0.000740, 0.000000], [0.001440, 0.000520, 0.000000], [0.001000, 0.000361, 0.000000],
+    [0.000690, 0.000249, 0.000000], [0.000476, 0.000172, 0.000000], [0.000332, 0.000120, 0.000000], [0.000235, 0.000085, 0.000000],
+    [0.000166, 0.000060, 0.000000], [0.000117, 0.000042, 0.000000], [0.000083, 0.000030, 0.000000], [0.000059, 0.000021, 0.000000],
+    [0.000042, 0.000015, 0.000000],
+];
+
+/// The `CIE 1964 10°` color matching functions `x̄₁₀`, `ȳ₁₀` and `z̄₁₀`, from `380nm` to `780nm` in `5nm` steps.
+const CIE1964: [[f64; 3]; SAMPLES] = [
+    [0.000160, 0.000017, 0.000705], [0.000662, 0.000072, 0.002928], [0.002362, 0.000253, 0.010482], [0.007242, 0.000769, 0.032344],
+    [0.019110, 0.002004, 0.086011], [0.043400, 0.004509, 0.197120], [0.084736, 0.008756, 0.389366], [0.140638, 0.014456, 0.656760],
+    [0.204492, 0.021391, 0.972542], [0.264737, 0.029497, 1.282500], [0.314679, 0.038676, 1.553480], [0.357719, 0.049602, 1.798500],
+    [0.383734, 0.062077, 1.967280], [0.386726, 0.074704, 2.027300], [0.370702, 0.089456, 1.994800], [0.342957, 0.106256, 1.900700],
+    [0.302273, 0.128201, 1.745370], [0.254085, 0.152761, 1.554900], [0.195618, 0.185190, 1.317560], [0.132349, 0.219940, 1.030200],
+    [0.080507, 0.253589, 0.772125], [0.041072, 0.297665, 0.570060], [0.016172, 0.339133, 0.415254], [0.005132, 0.395379, 0.302356],
+    [0.003816, 0.460777, 0.218502], [0.015444, 0.531360, 0.159249], [0.037465, 0.606741, 0.112044], [0.071358, 0.685660, 0.082248],
+    [0.117749, 0.761757, 0.060709], [0.172953, 0.823330, 0.043050], [0.236491, 0.875211, 0.030451], [0.304213, 0.923810, 0.020584],
+    [0.376772, 0.961988, 0.013676], [0.451584, 0.982200, 0.007918], [0.529826, 0.991761, 0.003988], [0.616053, 0.999110, 0.001091],
+    [0.705224, 0.997340, 0.000000], [0.793832, 0.982380, 0.000000], [0.878655, 0.955552, 0.000000], [0.951162, 0.915175, 0.000000],
+    [1.014160, 0.868934, 0.000000], [1.074300, 0.825623, 0.000000], [1.118520, 0.777405, 0.000000], [1.134300, 0.720353, 0.000000],
+    [1.123990, 0.658341, 0.000000], [1.089100, 0.593878, 0.000000], [1.030480, 0.527963, 0.000000], [0.950740, 0.461834, 0.000000],
+    [0.856297, 0.398057, 0.000000], [0.754930, 0.339554, 0.000000], [0.647467, 0.283493, 0.000000], [0.535110, 0.228254, 0.000000],
+    [0.431567, 0.179828, 0.000000], [0.343690, 0.140211, 0.000000], [0.268329, 0.107633, 0.000000], [0.204300, 0.081187, 0.000000],
+    [0.152568, 0.060281, 0.000000], [0.112210, 0.044096, 0.000000], [0.081261, 0.031800, 0.000000], [0.057930, 0.022602, 0.000000],
+    [0.040851, 0.015905, 0.000000], [0.028623, 0.011130, 0.000000], [0.019941, 0.007749, 0.000000], [0.013842, 0.005375, 0.000000],
+    [0.009577, 0.003718, 0.000000], [0.006605, 0.002565, 0.000000], [0.004553, 0.001768, 0.000000], [0.003145, 0.001222, 0.000000],
+    [0.002175, 0.000846, 0.000000], [0.001506, 0.000586, 0.000000], [0.001045, 0.000407, 0.000000], [0.000727, 0.000284, 0.000000],
+    [0.000508, 0.000199, 0.000000], [0.000356, 0.000140, 0.000000], [0.000251, 0.000098, 0.000000], [0.000178, 0.000070, 0.000000],
+    [0.000126, 0.000050, 0.000000], [0.000090, 0.000036, 0.000000], [0.000065, 0.000025, 0.000000], [0.000046, 0.000018, 0.000000],
+    [0.000033, 0.000013, 0.000000],
+];
+
+/// The daylight basis functions `S0`, `S1` and `S2` of the `CIE D` illuminants, from `380nm` to `780nm` in `10nm` steps.
+const DAYLIGHT: [[f32; 3]; 41] = [
+    [63.4, 38.5, 3.0], [65.8, 35.0, 1.2], [94.8, 43.4, -1.1], [104.8, 46.3, -0.5], [105.9, 43.9, -0.7],
+    [96.8, 37.1, -1.2], [113.9, 36.7, -2.6], [125.6, 35.9, -2.9], [125.5, 32.6, -2.8], [121.3, 27.9, -2.6],
+    [121.3, 24.3, -2.6], [113.5, 20.1, -1.8], [113.1, 16.2, -1.5], [110.8, 13.2, -1.3], [106.5, 8.6, -1.2],
+    [108.8, 6.1, -1.0], [105.3, 4.2, -0.5], [104.4, 1.9, -0.3], [100.0, 0.0, 0.0], [96.0, -1.6, 0.2],
+    [95.1, -3.5, 0.5], [89.1, -3.5, 2.1], [90.5, -5.8, 3.2], [90.3, -7.2, 4.1], [88.4, -8.6, 4.7],
+    [84.0, -9.5, 5.1], [85.1, -10.9, 6.7], [81.9, -10.7, 7.3], [82.6, -12.0, 8.6], [84.9, -14.0, 9.8],
+    [81.3, -13.6, 10.2], [71.9, -12.0, 8.3], [74.3, -13.3, 9.6], [76.4, -12.9, 8.5], [63.3, -10.6, 7.0],
+    [71.7, -11.6, 7.6], [77.0, -12.2, 8.0], [65.2, -10.2, 6.7], [47.7, -7.8, 5.2], [68.6, -11.2, 7.4],
+    [65.0, -10.4, 6.8],
+];
+
+/// The relative spectral power of `CIE F2`, from `380nm` to `780nm` in `5nm` steps.
+const F2: [f32; SAMPLES] = [
+    1.18, 1.48, 1.84, 2.15, 3.44, 15.69, 3.85, 3.74, 4.19, 4.62, 5.06, 34.98, 11.81, 6.27, 6.63, 6.93, 7.19, 7.40, 7.54, 7.62,
+    7.65, 7.62, 7.62, 7.45, 7.28, 7.15, 7.05, 7.04, 7.16, 7.47, 8.04, 8.88, 10.01, 24.88, 16.64, 14.59, 16.16, 17.56, 18.62, 21.47,
+    22.79, 19.29, 18.66, 17.73, 16.54, 15.21, 13.80, 12.36, 10.95, 9.65, 8.40, 7.32, 6.31, 5.43, 4.68, 4.02, 3.45, 2.96, 2.55, 2.19,
+    1.89, 1.64, 1.53, 1.27, 1.10, 0.99, 0.88, 0.76, 0.68, 0.61, 0.56, 0.54, 0.51, 0.47, 0.47, 0.43, 0.46, 0.47, 0.40, 0.33,
+    0.27,
+];
+
+/// The relative spectral power of `CIE F11`, from `380nm` to `780nm` in `5nm` steps.
+const F11: [f32; SAMPLES] = [
+    0.91, 0.63, 0.46, 0.37, 1.29, 12.68, 1.59, 1.79, 2.46, 3.33, 4.49, 33.94, 12.13, 6.95, 7.19, 7.12, 6.72, 6.13, 5.46, 4.79,
+    5.66, 14.29, 14.96, 8.97, 4.72, 2.33, 1.47, 1.10, 0.89, 0.83, 1.18, 4.90, 39.59, 72.84, 32.61, 7.52, 2.83, 1.96, 1.67, 4.43,
+    11.28, 14.76, 12.73, 9.74, 7.33, 9.72, 55.27, 42.58, 13.18, 13.16, 12.26, 5.11, 2.07, 2.34, 3.58, 3.01, 2.48, 2.14, 1.54, 1.33,
+    1.46, 1.94, 2.00, 1.20, 1.35, 4.10, 5.58, 2.51, 0.57, 0.27, 0.23, 0.21, 0.24, 0.24, 0.20, 0.24, 0.32, 0.26, 0.16, 0.12,
+    0.09,
+];
+
+/// A standard colorimetric observer, the color matching functions that turn a spectrum into `XYZ`.
+///
+/// - `CIE1931`: The `2°` observer, for small fields of view. This is the observer of `sRGB` and most color math.
+/// - `CIE1964`: The `10°` observer, for fields of view larger than about `4°`, e.g. paint and textile samples.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Observer {
+    CIE1931,
+    CIE1964,
+}
+
+impl Observer {
+    fn table(&self) -> &'static [[f64; 3]; SAMPLES] {
+        return match self {
+            Observer::CIE1931 => &CIE1931,
+            Observer::CIE1964 => &CIE1964,
+        };
+    }
+}
+
+/// A standard illuminant, the light a reflectance is seen under.
+///
+/// - `A`: Incandescent (tungsten) light, a black body of about `2856K`.
+/// - `D50`: Horizon daylight, about `5003K`, the illuminant of print and `ICC` profiles.
+/// - `D65`: Noon daylight, about `6504K`, the illuminant of `sRGB`.
+/// - `F2`: Cool white fluorescent light, about `4230K`.
+/// - `F11`: Narrow-band (tri-phosphor) white fluorescent light, about `4000K`.
+///
+/// The `D` illuminants are computed from the `CIE` daylight basis functions, and `A` from Planck's law, as the `CIE` defines them.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Illuminant {
+    A,
+    D50,
+    D65,
+    F2,
+    F11,
+}
+
+impl Illuminant {
+    /// The relative spectral power distribution of the illuminant, from `380nm` to `780nm` in `5nm` steps.
+    /// `A`, `D50` and `D65` are normalized to `100.0` at `560nm`, while `F2` and `F11` keep the relative values
+    /// the `CIE` publishes for them.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::Illuminant;
+    ///
+    /// assert!((Illuminant::D65.spectrum().value_at(560.0).unwrap() - 100.0).abs() < 0.01);
+    /// assert_eq!(Illuminant::F2.spectrum().value_at(560.0), Some(16.16));
+    /// ```
+    pub fn spectrum(&self) -> Spectrum {
+        return Spectrum { start: FIRST_NM, interval: STEP_NM, values: self.table().to_vec() };
+    }
+
+    /// The `XYZ` of the illuminant's white, seen by `observer` and normalized to `Y = 1.0`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{Illuminant, Observer};
+    ///
+    /// let [x, y, z] = Illuminant::D65.white_point(Observer::CIE1931);
+    /// assert!((x - 0.95047).abs() < 0.0005 && y == 1.0 && (z - 1.08883).abs() < 0.0005);
+    /// ```
+    pub fn white_point(&self, observer: Observer) -> [f32; 3] {
+        return integrate(self.table(), observer, |_| 1.0);
+    }
+
+    fn table(&self) -> &'static [f32; SAMPLES] {
+        static TABLES: OnceLock<[[f32; SAMPLES]; 3]> = OnceLock::new();
+        let tables = TABLES.get_or_init(|| [
+            std::array::from_fn(|i| planck_a(wavelength(i))),
+            daylight(5003.0),
+            daylight(6504.0),
+        ]);
+        return match self {
+            Illuminant::A => &tables[0],
+            Illuminant::D50 => &tables[1],
+            Illuminant::D65 => &tables[2],
+            Illuminant::F2 => &F2,
+            Illuminant::F11 => &F11,
+        };
+    }
+}
+
+/// A sampled spectrum, e.g. the power of a light source or the reflectance of a surface, measured at equally spaced wavelengths.
+///
+/// Any range and step can be used, e.g. `380-780nm` in `5nm` or `10nm` steps, or `400-700nm` in `10nm` steps, as many
+/// spectrophotometers measure. The spectrum is interpolated linearly to the `5nm` steps of the observer tables.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{Illuminant, Observer, Spectrum};
+///
+/// // A flat 50% reflectance, measured from 400nm to 700nm in 10nm steps
+/// let gray = Spectrum::new(400.0, 10.0, vec![0.5; 31]).unwrap();
+/// let color = gray.reflectance_to_color(Illuminant::D65, Observer::CIE1931);
+/// assert_eq!(color.to_hex(), "#bcbcbc");
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Spectrum {
+    start: f32,
+    interval: f32,
+    values: Vec<f32>,
+}
+
+impl Spectrum {
+    /// Create a spectrum from values measured every `interval` nanometers, starting at `start`.
+    ///
+    /// ## Returns
+    /// A `Result` containing either the spectrum, or a [`ColorFormatConverterError`]: `InvalidInputLength` if there are no values,
+    /// `NotANumber` if a value is `NaN`, or `OutOfRange` if `start` isn't positive, `interval` isn't positive, or a value is infinite.
+    pub fn new(start: f32, interval: f32, values: Vec<f32>) -> Result<Self, ColorFormatConverterError> {
+        if values.is_empty() {
+            return Err(ColorFormatConverterError::InvalidInputLength);
+        }
+        if start.is_nan() || interval.is_nan() || values.iter().any(|v| v.is_nan()) {
+            return Err(ColorFormatConverterError::NotANumber);
+        }
+        if !(start > 0.0 && start.is_finite() && interval > 0.0 && interval.is_finite()) || values.iter().any(|v| v.is_infinite()) {
+            return Err(ColorFormatConverterError::OutOfRange);
+        }
+        return Ok(Self { start, interval, values });
+    }
+
+    /// The wavelength of the first value, in nanometers.
+    pub fn start(&self) -> f32 {
+        return self.start;
+    }
+
+    /// The step between two values, in nanometers.
+    pub fn interval(&self) -> f32 {
+        return self.interval;
+    }
+
+    /// The measured values, either relative power or reflectance (`0.0-1.0`).
+    pub fn values(&self) -> &[f32] {
+        return &self.values;
+    }
+
+    /// The value at a wavelength, interpolated linearly. Outside of the measured range, this is `None`.
+    pub fn value_at(&self, wavelength: f32) -> Option<f32> {
+        let position = (wavelength - self.start) / self.interval;
+        let last = (self.values.len() - 1) as f32;
+        if !(0.0..=last).contains(&position) {
+            return None;
+        }
+        let i = (position as usize).min(self.values.len().saturating_sub(2));
+        let next = self.values.get(i + 1).copied().unwrap_or(self.values[i]);
+        return Some(self.values[i] + (next - self.values[i]) * (position - i as f32));
+    }
+
+    /// The `XYZ` of a light source with this spectral power distribution, normalized to `Y = 1.0`.
+    /// No power is assumed outside of the measured range.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{Illuminant, Observer};
+    ///
+    /// let a = Illuminant::A.spectrum().emission_to_xyz(Observer::CIE1931);
+    /// let chromaticity = [a[0] / (a[0] + a[1] + a[2]), a[1] / (a[0] + a[1] + a[2])];
+    /// assert!((chromaticity[0] - 0.44757).abs() < 0.0005 && (chromaticity[1] - 0.40745).abs() < 0.0005);
+    /// ```
+    pub fn emission_to_xyz(&self, observer: Observer) -> [f32; 3] {
+        let power: [f32; SAMPLES] = std::array::from_fn(|i| self.value_at(wavelength(i)).unwrap_or(0.0));
+        return integrate(&power, observer, |_| 1.0);
+    }
+
+    /// The `XYZ` of a surface with this spectral reflectance (`0.0-1.0`), seen under `illuminant`.
+    ///
+    /// The result is relative to the illuminant, so a perfect white reflector has the `XYZ` of [`Illuminant::white_point`].
+    /// Outside of the measured range, the nearest measured value is used, as the `CIE` recommends.
+    pub fn reflectance_to_xyz(&self, illuminant: Illuminant, observer: Observer) -> [f32; 3] {
+        let last = self.values[self.values.len() - 1];
+        return integrate(illuminant.table(), observer, |nm| {
+            self.value_at(nm).unwrap_or(if nm < self.start { self.values[0] } else { last })
+        });
+    }
+
+    /// The color of a surface with this spectral reflectance, seen under `illuminant`, as an `sRGB` [`Color`].
+    ///
+    /// The `XYZ` from [`Spectrum::reflectance_to_xyz`] is adapted from the white of the illuminant to `D65` with
+    /// the Bradford transform, as if the eye was adapted to the illuminant. The color can be converted to any other model
+    /// from there, e.g. with [`Color::to_pixel`]. Colors outside of the `sRGB` gamut keep their values outside of `0.0-1.0`.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// # use luna::color_format_converter::{ColorFormat, Illuminant, Observer, Spectrum};
+    ///
+    /// // A neutral 50% gray card under tungsten light still looks neutral to an adapted eye
+    /// let card = Spectrum::new(380.0, 10.0, vec![0.5; 41]).unwrap();
+    /// let lab = card.reflectance_to_color(Illuminant::A, Observer::CIE1964).to_pixel::<f32>(ColorFormat::Lab).unwrap();
+    /// assert!((lab[0] - 76.07).abs() < 0.01 && lab[1].abs() < 0.01 && lab[2].abs() < 0.01);
+    /// ```
+    pub fn reflectance_to_color(&self, illuminant: Illuminant, observer: Observer) -> Color {
+        let xyz = self.reflectance_to_xyz(illuminant, observer);
+        let (x, y, z) = WhitePoint::D65.xyz();
+        let adapted = spaces::adapt_xyz(xyz, illuminant.white_point(observer), [x, y, z]);
+        let [r, g, b] = spaces::linear_to_srgb(spaces::xyz_to_linear_srgb(adapted));
+        return Color::new(r, g, b, 1.0);
+    }
+}
+
+/// The wavelength of an entry of the built-in tables.
+fn wavelength(i: usize) -> f32 {
+    return FIRST_NM + STEP_NM * i as f32;
+}
+
+/// Sums `power × factor × observer` over the built-in wavelengths, normalized so that `power × observer` has `Y = 1.0`.
+fn integrate(power: &[f32; SAMPLES], observer: Observer, factor: impl Fn(f32) -> f32) -> [f32; 3] {
+    let mut xyz = [0.0f64; 3];
+    let mut white_y = 0.0f64;
+    for (i, (&p, cmf)) in power.iter().zip(observer.table()).enumerate() {
+        let f = factor(wavelength(i)) as f64;
+        for (sum, &c) in xyz.iter_mut().zip(cmf) {
+            *sum += p as f64 * f * c;
+        }
+        white_y += p as f64 * cmf[1];
+    }
+    if white_y <= 0.0 {
+        return [0.0; 3];
+    }
+    return xyz.map(|v| (v / white_y) as f32);
+}
+
+/// `CIE` illuminant `A`, normalized to `100.0` at `560nm`.
+fn planck_a(nm: f32) -> f32 {
+    let c2 = 1.435e7_f64;
+    let t = 2848.0;
+    let nm = nm as f64;
+    return (100.0 * (560.0 / nm).powi(5) * ((c2 / (t * 560.0)).exp() - 1.0) / ((c2 / (t * nm)).exp() - 1.0)) as f32;
+}
+
+/// A `CIE D` illuminant of a correlated color temperature, from the daylight basis functions,
+/// interpolated linearly to `5nm` steps.
+fn daylight(kelvin: f64) -> [f32; SAMPLES] {
+    let x = if kelvin <= 7000.0 {
+        -4.6070e9 / kelvin.powi(3) + 2.9678e6 / kelvin.powi(2) + 0.09911e3 / kelvin + 0.244063
+    } else {
+        -2.0064e9 / kelvin.powi(3) + 1.9018e6 / kelvin.powi(2) + 0.24748e3 / kelvin + 0.237040
+    };
+    let y = -3.000 * x * x + 2.870 * x - 0.275;
+    let m = 0.0241 + 0.2562 * x - 0.7341 * y;
+    // the CIE rounds the factors to 3 decimals
+    let m1 = ((-1.3515 - 1.7703 * x + 5.9114 * y) / m * 1000.0).round() / 1000.0;
+    let m2 = ((0.0300 - 31.4424 * x + 30.0717 * y) / m * 1000.0).round() / 1000.0;
+
+    let at = |i: usize| {
+        let [s0, s1, s2] = DAYLIGHT[i].map(|v| v as f64);
+        s0 + m1 * s1 + m2 * s2
+    };
+    return std::array::from_fn(|i| {
+        let value = if i % 2 == 0 { at(i / 2) } else { (at(i / 2) + at(i / 2 + 1)) / 2.0 };
+        value as f32
+    });
+}
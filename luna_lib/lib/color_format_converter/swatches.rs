@@ -0,0 +1,439 @@
+//! Palette files: reading and writing swatches in the formats of GIMP, Adobe, Paint.NET and plain hex lists.
+//!
+//! Swatches keep the color model they were stored in, so a `CMYK` swatch read from one Adobe file is written to another
+//! unchanged. Formats that only hold `RGB` colors get the `sRGB` value of other swatches.
+
+use std::fmt::Write;
+
+use super::{spaces, Color, ColorFormat, ColorFormatConverterError};
+
+/// The `ACO` color spaces supported, by their id.
+const ACO_RGB: u16 = 0;
+const ACO_HSB: u16 = 1;
+const ACO_CMYK: u16 = 2;
+const ACO_LAB: u16 = 7;
+const ACO_GRAY: u16 = 8;
+
+/// The `ASE` block types.
+const ASE_COLOR: u16 = 0x0001;
+/// The `ASE` color type of a normal (process, not global or spot) color.
+const ASE_NORMAL: u16 = 2;
+
+/// A palette file format.
+///
+/// - `Gpl`: GIMP palette (`.gpl`), 8-bit `RGB` colors with names. Also used by Inkscape and Krita.
+/// - `Ase`: Adobe Swatch Exchange (`.ase`), `RGB`, `CMYK`, `Lab` or `Gray` colors with names, from Illustrator, InDesign and Photoshop.
+///   Groups are read as if their colors weren't grouped.
+/// - `Aco`: Photoshop color swatches (`.aco`), `RGB`, `HSB` (read as `RGB`), `CMYK`, `Lab` or `Gray` colors, with names in version 2 files.
+/// - `PaintNet`: Paint.NET palette (`.txt`), one `AARRGGBB` hex color per line, without names. Alpha is ignored.
+/// - `Hex`: A plain list of hex colors, one `#rrggbb` or `rrggbb` per line, e.g. from Lospec or Coolors.
+///   Text after the color is read as its name, but names aren't written.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PaletteFormat {
+    Gpl,
+    Ase,
+    Aco,
+    PaintNet,
+    Hex,
+}
+
+/// The color of a [`Swatch`], in the model it's stored in.
+///
+/// - `Rgb`: `sRGB` encoded red, green and blue, `0.0-1.0`.
+/// - `Cmyk`: Cyan, magenta, yellow and black ink, `0.0-1.0`, using the naive `CMYK` model of this module.
+/// - `Lab`: `CIE L*a*b*` relative to `D50`, with `L` in `0-100`, like Adobe's `Lab`.
+/// - `Gray`: The gray level, `0.0` (black) to `1.0` (white).
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SwatchColor {
+    Rgb([f32; 3]),
+    Cmyk([f32; 4]),
+    Lab([f32; 3]),
+    Gray(f32),
+}
+
+impl SwatchColor {
+    /// Converts the swatch color to an opaque `sRGB` [`Color`].
+    pub fn to_color(&self) -> Color {
+        let [r, g, b] = match *self {
+            SwatchColor::Rgb(rgb) => rgb,
+            SwatchColor::Cmyk(cmyk) => {
+                let color = Color::from_pixel(&cmyk.map(|v| v.clamp(0.0, 1.0)), ColorFormat::CMYK).expect("CMYK values are clamped.");
+                [color.r, color.g, color.b]
+            },
+            SwatchColor::Lab(lab) => spaces::lab_to_srgb(lab),
+            SwatchColor::Gray(gray) => [gray; 3],
+        };
+        return Color::new(r, g, b, 1.0);
+    }
+}
+
+/// A named color of a palette.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::{read_palette, write_palette, PaletteFormat, Swatch, SwatchColor};
+///
+/// let swatches = vec![
+///     Swatch { name: "Paper".to_string(), color: SwatchColor::Rgb([1.0, 1.0, 1.0]) },
+///     Swatch { name: "Process Cyan".to_string(), color: SwatchColor::Cmyk([1.0, 0.0, 0.0, 0.0]) },
+/// ];
+///
+/// // Adobe files keep the CMYK swatch as it is
+/// let ase = write_palette(&swatches, PaletteFormat::Ase).unwrap();
+/// assert_eq!(read_palette(&ase, PaletteFormat::Ase).unwrap(), swatches);
+///
+/// // GIMP palettes only hold RGB
+/// let gpl = write_palette(&swatches, PaletteFormat::Gpl).unwrap();
+/// assert_eq!(String::from_utf8(gpl).unwrap(), "GIMP Palette\n#\n255 255 255\tPaper\n  0 255 255\tProcess Cyan\n");
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Swatch {
+    /// The name of the swatch, empty for formats without names.
+    pub name: String,
+    /// The color of the swatch.
+    pub color: SwatchColor,
+}
+
+/// Read the swatches of a palette file.
+///
+/// ## Parameters
+/// - `data`: the contents of the file
+/// - `format`: the format of the file
+///
+/// ## Returns
+/// A `Result` containing either the swatches in the order of the file, or a [`ColorFormatConverterError`]:
+/// `InvalidPalette` if the file is malformed, or `UnsupportedFormat` for `ACO` color spaces other than `RGB`, `HSB`, `CMYK`,
+/// `Lab` and `Gray` (e.g. Pantone).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{read_palette, PaletteFormat, SwatchColor};
+///
+/// let gpl = "GIMP Palette\nName: Primaries\nColumns: 3\n#\n255   0   0\tRed\n  0   0 255\tBlue\n";
+/// let swatches = read_palette(gpl.as_bytes(), PaletteFormat::Gpl).unwrap();
+/// assert_eq!(swatches[1].name, "Blue");
+/// assert_eq!(swatches[1].color, SwatchColor::Rgb([0.0, 0.0, 1.0]));
+///
+/// let hex = "#ff8000 Orange\n663399\n";
+/// let swatches = read_palette(hex.as_bytes(), PaletteFormat::Hex).unwrap();
+/// assert_eq!(swatches[0].name, "Orange");
+/// assert_eq!(swatches[1].color.to_color().to_hex(), "#663399");
+///
+/// // Lines starting with `#` that aren't colors are comments
+/// let commented = "# My palette\n#ff0000 Red\n# 00ff00 is not used\n";
+/// let swatches = read_palette(commented.as_bytes(), PaletteFormat::Hex).unwrap();
+/// assert_eq!(swatches.len(), 1);
+/// assert_eq!(swatches[0].name, "Red");
+/// ```
+pub fn read_palette(data: &[u8], format: PaletteFormat) -> Result<Vec<Swatch>, ColorFormatConverterError> {
+    let text = || std::str::from_utf8(data).map_err(|_| ColorFormatConverterError::InvalidPalette);
+    return match format {
+        PaletteFormat::Gpl => read_gpl(text()?),
+        PaletteFormat::Ase => read_ase(data),
+        PaletteFormat::Aco => read_aco(data),
+        PaletteFormat::PaintNet => read_hex_lines(text()?, ';', 8),
+        PaletteFormat::Hex => read_hex_lines(text()?, '#', 6),
+    };
+}
+
+/// Write swatches as a palette file.
+///
+/// `Gpl`, `PaintNet` and `Hex` only hold 8-bit `RGB`, so other swatches are written as their `sRGB` value, see [`SwatchColor::to_color`].
+/// `Ase` and `Aco` keep the color model of every swatch, except that `ACO` stores channels as 16-bit integers.
+///
+/// ## Parameters
+/// - `swatches`: the swatches to write
+/// - `format`: the format to write
+///
+/// ## Returns
+/// A `Result` containing either the contents of the file, or `InvalidPalette` if the swatches don't fit the format:
+/// more than `65535` swatches for `ACO`, a name longer than `65534` `UTF-16` units for `ASE`, or a line break in a `GPL` name.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{read_palette, write_palette, PaletteFormat, Swatch, SwatchColor};
+///
+/// let swatches = vec![Swatch { name: String::new(), color: SwatchColor::Gray(0.5) }];
+/// let text = write_palette(&swatches, PaletteFormat::PaintNet).unwrap();
+/// assert!(String::from_utf8(text).unwrap().ends_with("FF808080\n"));
+///
+/// let aco = write_palette(&swatches, PaletteFormat::Aco).unwrap();
+/// assert_eq!(read_palette(&aco, PaletteFormat::Aco).unwrap(), swatches);
+/// ```
+pub fn write_palette(swatches: &[Swatch], format: PaletteFormat) -> Result<Vec<u8>, ColorFormatConverterError> {
+    return match format {
+        PaletteFormat::Gpl => write_gpl(swatches),
+        PaletteFormat::Ase => write_ase(swatches),
+        PaletteFormat::Aco => write_aco(swatches),
+        PaletteFormat::PaintNet => {
+            let mut text = "; paint.net Palette File\n; Lines that start with a semicolon are comments\n".to_string();
+            for swatch in swatches {
+                let [r, g, b] = swatch.color.to_color().to_rgb8();
+                let _ = writeln!(text, "FF{:02X}{:02X}{:02X}", r, g, b);
+            }
+            Ok(text.into_bytes())
+        },
+        PaletteFormat::Hex => {
+            let mut text = String::new();
+            for swatch in swatches {
+                let _ = writeln!(text, "{}", swatch.color.to_color().to_hex());
+            }
+            Ok(text.into_bytes())
+        },
+    };
+}
+
+fn read_gpl(text: &str) -> Result<Vec<Swatch>, ColorFormatConverterError> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(ColorFormatConverterError::InvalidPalette);
+    }
+
+    let mut swatches = Vec::new();
+    for line in lines {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+        let mut rest = line;
+        let mut rgb = [0.0; 3];
+        for value in rgb.iter_mut() {
+            rest = rest.trim_start();
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let channel: u8 = rest[..end].parse().map_err(|_| ColorFormatConverterError::InvalidPalette)?;
+            *value = channel as f32 / 255.0;
+            rest = &rest[end..];
+        }
+        swatches.push(Swatch { name: rest.trim().to_string(), color: SwatchColor::Rgb(rgb) });
+    }
+    return Ok(swatches);
+}
+
+fn write_gpl(swatches: &[Swatch]) -> Result<Vec<u8>, ColorFormatConverterError> {
+    let mut text = "GIMP Palette\n#\n".to_string();
+    for swatch in swatches {
+        if swatch.name.contains(['\n', '\r']) {
+            return Err(ColorFormatConverterError::InvalidPalette);
+        }
+        let [r, g, b] = swatch.color.to_color().to_rgb8();
+        let _ = writeln!(text, "{:3} {:3} {:3}\t{}", r, g, b, swatch.name);
+    }
+    return Ok(text.into_bytes());
+}
+
+/// Reads lines of hex colors of `digits` digits (`AARRGGBB` or `RRGGBB`), skipping lines starting with `comment`.
+/// If `comment` is `#`, a `#` followed by a color starts the color instead. Text after the color is the name.
+fn read_hex_lines(text: &str, comment: char, digits: usize) -> Result<Vec<Swatch>, ColorFormatConverterError> {
+    let is_color = |line: &str| {
+        let hex = line.split(char::is_whitespace).next().unwrap_or("");
+        return hex.len() == digits && hex.chars().all(|c| c.is_ascii_hexdigit());
+    };
+
+    let mut swatches = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        let line = match line.strip_prefix('#') {
+            Some(color) if comment == '#' && is_color(color) => color,
+            _ if line.is_empty() || line.starts_with(comment) => continue,
+            _ => line,
+        };
+        let (hex, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if hex.len() != digits || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorFormatConverterError::InvalidPalette);
+        }
+        let value = u32::from_str_radix(hex, 16).map_err(|_| ColorFormatConverterError::InvalidPalette)?;
+        let rgb = [16, 8, 0].map(|shift| ((value >> shift) & 0xff) as f32 / 255.0);
+        swatches.push(Swatch { name: name.trim().to_string(), color: SwatchColor::Rgb(rgb) });
+    }
+    return Ok(swatches);
+}
+
+/// Reads big-endian values from a binary file.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], ColorFormatConverterError> {
+        if self.data.len() < count {
+            return Err(ColorFormatConverterError::InvalidPalette);
+        }
+        let (bytes, rest) = self.data.split_at(count);
+        self.data = rest;
+        return Ok(bytes);
+    }
+
+    fn u16(&mut self) -> Result<u16, ColorFormatConverterError> {
+        return Ok(u16::from_be_bytes(self.bytes(2)?.try_into().expect("2 bytes")));
+    }
+
+    fn u32(&mut self) -> Result<u32, ColorFormatConverterError> {
+        return Ok(u32::from_be_bytes(self.bytes(4)?.try_into().expect("4 bytes")));
+    }
+
+    fn f32(&mut self) -> Result<f32, ColorFormatConverterError> {
+        return Ok(f32::from_be_bytes(self.bytes(4)?.try_into().expect("4 bytes")));
+    }
+
+    /// A string of `units` `UTF-16` code units, without its trailing null terminator.
+    fn utf16(&mut self, units: usize) -> Result<String, ColorFormatConverterError> {
+        let bytes = self.bytes(units * 2)?;
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+        let text = String::from_utf16(&units).map_err(|_| ColorFormatConverterError::InvalidPalette)?;
+        return Ok(text.trim_end_matches('\0').to_string());
+    }
+}
+
+/// Appends a string as `UTF-16` code units with a null terminator, returning the amount of units.
+fn push_utf16(out: &mut Vec<u8>, text: &str) -> usize {
+    let units: Vec<u16> = text.encode_utf16().chain([0]).collect();
+    out.extend(units.iter().flat_map(|u| u.to_be_bytes()));
+    return units.len();
+}
+
+fn read_ase(data: &[u8]) -> Result<Vec<Swatch>, ColorFormatConverterError> {
+    let mut reader = Reader { data };
+    if reader.bytes(4)? != b"ASEF" || reader.u16()? != 1 {
+        return Err(ColorFormatConverterError::InvalidPalette);
+    }
+    let _minor = reader.u16()?;
+    let blocks = reader.u32()?;
+
+    let mut swatches = Vec::new();
+    for _ in 0..blocks {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = Reader { data: reader.bytes(length)? };
+        // groups are flattened, and other blocks skipped
+        if kind != ASE_COLOR {
+            continue;
+        }
+        let units = block.u16()? as usize;
+        let name = block.utf16(units)?;
+        let color = match block.bytes(4)? {
+            b"RGB " => SwatchColor::Rgb([block.f32()?, block.f32()?, block.f32()?]),
+            b"CMYK" => SwatchColor::Cmyk([block.f32()?, block.f32()?, block.f32()?, block.f32()?]),
+            b"LAB " => SwatchColor::Lab([block.f32()? * 100.0, block.f32()?, block.f32()?]),
+            b"Gray" => SwatchColor::Gray(block.f32()?),
+            _ => return Err(ColorFormatConverterError::InvalidPalette),
+        };
+        swatches.push(Swatch { name, color });
+    }
+    return Ok(swatches);
+}
+
+fn write_ase(swatches: &[Swatch]) -> Result<Vec<u8>, ColorFormatConverterError> {
+    let mut out = b"ASEF".to_vec();
+    out.extend(1u16.to_be_bytes());
+    out.extend(0u16.to_be_bytes());
+    out.extend((swatches.len() as u32).to_be_bytes());
+
+    for swatch in swatches {
+        let mut block = Vec::new();
+        let mut name = Vec::new();
+        let units = push_utf16(&mut name, &swatch.name);
+        let units = u16::try_from(units).map_err(|_| ColorFormatConverterError::InvalidPalette)?;
+        block.extend(units.to_be_bytes());
+        block.extend(name);
+
+        let (model, values): (&[u8; 4], Vec<f32>) = match swatch.color {
+            SwatchColor::Rgb(rgb) => (b"RGB ", rgb.to_vec()),
+            SwatchColor::Cmyk(cmyk) => (b"CMYK", cmyk.to_vec()),
+            SwatchColor::Lab([l, a, b]) => (b"LAB ", vec![l / 100.0, a, b]),
+            SwatchColor::Gray(gray) => (b"Gray", vec![gray]),
+        };
+        block.extend(model);
+        block.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+        block.extend(ASE_NORMAL.to_be_bytes());
+
+        out.extend(ASE_COLOR.to_be_bytes());
+        out.extend((block.len() as u32).to_be_bytes());
+        out.extend(block);
+    }
+    return Ok(out);
+}
+
+fn read_aco(data: &[u8]) -> Result<Vec<Swatch>, ColorFormatConverterError> {
+    let mut reader = Reader { data };
+    let mut version = reader.u16()?;
+    if version != 1 && version != 2 {
+        return Err(ColorFormatConverterError::InvalidPalette);
+    }
+
+    let mut swatches = Vec::new();
+    loop {
+        let count = reader.u16()?;
+        swatches.clear();
+        for _ in 0..count {
+            let space = reader.u16()?;
+            let values = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+            let name = if version == 2 {
+                let units = reader.u32()? as usize;
+                reader.utf16(units)?
+            } else {
+                String::new()
+            };
+            swatches.push(Swatch { name, color: aco_color(space, values)? });
+        }
+        // version 1 data is usually followed by the same colors with names, as version 2
+        if version == 2 || reader.data.is_empty() {
+            break;
+        }
+        version = reader.u16()?;
+        if version != 2 {
+            return Err(ColorFormatConverterError::InvalidPalette);
+        }
+    }
+    return Ok(swatches);
+}
+
+fn aco_color(space: u16, values: [u16; 4]) -> Result<SwatchColor, ColorFormatConverterError> {
+    let [w, x, y, z] = values.map(|v| v as f32 / 65535.0);
+    return Ok(match space {
+        ACO_RGB => SwatchColor::Rgb([w, x, y]),
+        ACO_HSB => {
+            // HSV, with the value as the lightness of the brightest channel
+            let lightness = y * (1.0 - x / 2.0);
+            let saturation = if lightness <= 0.0 || lightness >= 1.0 { 0.0 } else { (y - lightness) / lightness.min(1.0 - lightness) };
+            let color = Color::from_hsl(w * 360.0, saturation, lightness, 1.0);
+            SwatchColor::Rgb([color.r, color.g, color.b])
+        },
+        // 0 is full ink
+        ACO_CMYK => SwatchColor::Cmyk([1.0 - w, 1.0 - x, 1.0 - y, 1.0 - z]),
+        ACO_LAB => SwatchColor::Lab([values[0] as f32 / 100.0, values[1] as i16 as f32 / 100.0, values[2] as i16 as f32 / 100.0]),
+        // the amount of black ink, from 0 to 10000
+        ACO_GRAY => SwatchColor::Gray(1.0 - values[0] as f32 / 10000.0),
+        _ => return Err(ColorFormatConverterError::UnsupportedFormat),
+    });
+}
+
+fn write_aco(swatches: &[Swatch]) -> Result<Vec<u8>, ColorFormatConverterError> {
+    let count = u16::try_from(swatches.len()).map_err(|_| ColorFormatConverterError::InvalidPalette)?;
+    let scale = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max).round() as u16;
+    let entries: Vec<(u16, [u16; 4])> = swatches.iter().map(|swatch| match swatch.color {
+        SwatchColor::Rgb(rgb) => (ACO_RGB, [scale(rgb[0], 65535.0), scale(rgb[1], 65535.0), scale(rgb[2], 65535.0), 0]),
+        SwatchColor::Cmyk(cmyk) => (ACO_CMYK, cmyk.map(|v| scale(1.0 - v, 65535.0))),
+        SwatchColor::Lab([l, a, b]) => {
+            let signed = |v: f32| (v * 100.0).round().clamp(-12800.0, 12700.0) as i16 as u16;
+            (ACO_LAB, [(l * 100.0).round().clamp(0.0, 10000.0) as u16, signed(a), signed(b), 0])
+        },
+        SwatchColor::Gray(gray) => (ACO_GRAY, [scale(1.0 - gray, 10000.0), 0, 0, 0]),
+    }).collect();
+
+    let mut out = Vec::new();
+    for version in [1u16, 2] {
+        out.extend(version.to_be_bytes());
+        out.extend(count.to_be_bytes());
+        for ((space, values), swatch) in entries.iter().zip(swatches) {
+            out.extend(space.to_be_bytes());
+            out.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+            if version == 2 {
+                let mut name = Vec::new();
+                let units = push_utf16(&mut name, &swatch.name);
+                out.extend((units as u32).to_be_bytes());
+                out.extend(name);
+            }
+        }
+    }
+    return Ok(out);
+}
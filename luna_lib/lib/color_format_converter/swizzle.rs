@@ -0,0 +1,308 @@
+//! Channel reordering (swizzling) of pixels, within one color family (e.g. `RGBA` to `BGR`), or freely with a swizzle pattern.
+//!
+//! The source of every output channel is looked up once per pair of formats in a [`SwizzleMap`], instead of once per pixel.
+//! The common `RGB(A)` and `BGR(A)` conversions have their own paths on fixed-size pixels, which the compiler unrolls
+//! and turns into `SIMD` shuffles. Formats with the same channels can also be reordered in place.
+
+use super::{check_values, models, Channel, ChannelType, ColorFormat, ColorFormatConverterError};
+
+/// The most channels any format has, e.g. `CMYKA`.
+const MAX_CHANNELS: usize = 5;
+
+/// Extract one channel of a buffer as a `Gray` buffer, e.g. to edit or inspect an alpha mask.
+///
+/// The values are copied as they are, so extracting e.g. `R` gives the red values as gray levels,
+/// not the luminance of the red part.
+///
+/// ## Parameters
+/// - `data`: input channel values, length must be a multiple of `format.channel_count()`
+/// - `format`: color model of `data`, which can't be a packed format
+/// - `channel`: the channel to extract
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<T>` with one value per pixel, or a [`ColorFormatConverterError`]:
+/// `UnsupportedFormat` if `format` is packed or doesn't have the channel, `InvalidInputLength` if `data` has a wrong length,
+/// or `NotANumber` or `OutOfRange` for invalid float values.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{extract_channel, Channel, ColorFormat, ColorFormatConverterError};
+///
+/// let bgra: Vec<u8> = vec![10, 20, 30, 255,  40, 50, 60, 128];
+/// assert_eq!(extract_channel(&bgra, ColorFormat::BGRA, Channel::R), Ok(vec![30, 60]));
+/// assert_eq!(extract_channel(&bgra, ColorFormat::BGRA, Channel::A), Ok(vec![255, 128]));
+///
+/// let err = extract_channel(&bgra, ColorFormat::BGRA, Channel::K);
+/// assert_eq!(err, Err(ColorFormatConverterError::UnsupportedFormat));
+/// ```
+pub fn extract_channel<T: ChannelType>(data: &[T], format: ColorFormat, channel: Channel) -> Result<Vec<T>, ColorFormatConverterError> {
+    check_format(data, format)?;
+    let idx = channel_index(format, channel)?;
+
+    let mut sources = [Source::Max; MAX_CHANNELS];
+    sources[0] = Source::Channel(idx);
+    let map = SwizzleMap { input: format.channel_count(), output: 1, sources };
+
+    let mut out = Vec::with_capacity(data.len() / map.input);
+    map.apply(data, &mut out);
+    return Ok(out);
+}
+
+/// Replace one channel of a buffer in place with the values of a `Gray` buffer, e.g. to apply an edited alpha mask.
+///
+/// ## Parameters
+/// - `data`: channel values, length must be a multiple of `format.channel_count()`
+/// - `format`: color model of `data`, which can't be a packed format
+/// - `channel`: the channel to replace
+/// - `values`: the new values of the channel, one per pixel
+///
+/// ## Returns
+/// An empty `Result`, or a [`ColorFormatConverterError`]: `UnsupportedFormat` if `format` is packed or doesn't have the channel,
+/// `InvalidInputLength` if `values` doesn't have one value per pixel of `data`, or `NotANumber` or `OutOfRange`
+/// for invalid float values. On error, `data` is unchanged.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{extract_channel, replace_channel, Channel, ColorFormat};
+///
+/// let mut rgba: Vec<u8> = vec![10, 20, 30, 255,  40, 50, 60, 255];
+///
+/// // Copy the green channel into alpha
+/// let green = extract_channel(&rgba, ColorFormat::RGBA, Channel::G).unwrap();
+/// replace_channel(&mut rgba, ColorFormat::RGBA, Channel::A, &green).unwrap();
+/// assert_eq!(rgba, vec![10, 20, 30, 20,  40, 50, 60, 50]);
+/// ```
+pub fn replace_channel<T: ChannelType>(data: &mut [T], format: ColorFormat, channel: Channel, values: &[T]) -> Result<(), ColorFormatConverterError> {
+    check_format(data, format)?;
+    let idx = channel_index(format, channel)?;
+    let channels = format.channel_count();
+
+    if values.len() != data.len() / channels {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    if T::IS_FLOAT {
+        check_values(values, format)?;
+    }
+
+    for (pixel, &value) in data.chunks_exact_mut(channels).zip(values) {
+        pixel[idx] = value;
+    }
+    return Ok(());
+}
+
+/// Reorder, copy, drop and fill the channels of every pixel freely, with a swizzle pattern like the ones of shader languages.
+///
+/// Every character of the pattern is one output channel, so the output can have from 1 to 5 channels:
+/// - the letter of a channel of `format` copies that channel, see [`Channel`] for the letters, e.g. `R` or `A`
+/// - `0` fills the channel with `0`
+/// - `1` fills the channel with `ChannelType::MAX`, meaning `255`, `65535` or `1.0`
+///
+/// The output often matches a [`ColorFormat`], e.g. `"BGR1"` gives `BGRA` with opaque alpha,
+/// but doesn't have to, e.g. `"RRRA"` spreads the red channel to a gray-looking `RGBA` buffer.
+///
+/// ## Parameters
+/// - `data`: input channel values, length must be a multiple of `format.channel_count()`
+/// - `format`: color model of `data`, which can't be a packed format
+/// - `pattern`: the swizzle pattern
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<T>` with `pattern.len()` values per pixel, or a [`ColorFormatConverterError`]:
+/// `InvalidSwizzle` if the pattern is empty, longer than 5 characters, or has a letter `format` doesn't have,
+/// `UnsupportedFormat` for packed formats, `InvalidInputLength` if `data` has a wrong length,
+/// or `NotANumber` or `OutOfRange` for invalid float values.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{swizzle, ColorFormat, ColorFormatConverterError};
+///
+/// let rgba: Vec<u8> = vec![10, 20, 30, 40];
+/// assert_eq!(swizzle(&rgba, ColorFormat::RGBA, "BGR1"), Ok(vec![30, 20, 10, 255]));
+/// assert_eq!(swizzle(&rgba, ColorFormat::RGBA, "RRRA"), Ok(vec![10, 10, 10, 40]));
+/// assert_eq!(swizzle(&rgba, ColorFormat::RGBA, "A"), Ok(vec![40]));
+///
+/// // Lab channels use lowercase letters for a and b
+/// let lab: Vec<f32> = vec![50.0, 20.0, -30.0];
+/// assert_eq!(swizzle(&lab, ColorFormat::Lab, "L00"), Ok(vec![50.0, 0.0, 0.0]));
+///
+/// let err = swizzle(&rgba, ColorFormat::RGBA, "CMYK");
+/// assert_eq!(err, Err(ColorFormatConverterError::InvalidSwizzle));
+/// ```
+pub fn swizzle<T: ChannelType>(data: &[T], format: ColorFormat, pattern: &str) -> Result<Vec<T>, ColorFormatConverterError> {
+    check_format(data, format)?;
+    let map = SwizzleMap::from_pattern(format.channels(), pattern).ok_or(ColorFormatConverterError::InvalidSwizzle)?;
+
+    let mut out = Vec::with_capacity(data.len() / map.input * map.output);
+    map.apply(data, &mut out);
+    return Ok(out);
+}
+
+/// Checks a buffer for the channel functions, which work on any format that isn't packed.
+fn check_format<T: ChannelType>(data: &[T], format: ColorFormat) -> Result<(), ColorFormatConverterError> {
+    if format.is_packed() || (!T::IS_FLOAT && models::family(format).is_unbounded()) {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    if data.is_empty() || !data.len().is_multiple_of(format.channel_count()) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    if T::IS_FLOAT {
+        check_values(data, format)?;
+    }
+    return Ok(());
+}
+
+fn channel_index(format: ColorFormat, channel: Channel) -> Result<usize, ColorFormatConverterError> {
+    return format.channels().iter().position(|&c| c == channel).ok_or(ColorFormatConverterError::UnsupportedFormat);
+}
+
+/// Where an output channel comes from.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Source {
+    /// The channel at this index of the input pixel.
+    Channel(usize),
+    /// A fully saturated channel, `ChannelType::MAX`, e.g. a missing alpha channel.
+    Max,
+    /// An empty channel, `0`.
+    Zero,
+}
+
+/// The source of every output channel for a pair of channel orders.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(super) struct SwizzleMap {
+    input: usize,
+    output: usize,
+    sources: [Source; MAX_CHANNELS],
+}
+
+impl SwizzleMap {
+    /// Maps the channels of `from` to the channels of `to`. Channels of `to` missing in `from` must be alpha.
+    pub(super) fn new(from: &[Channel], to: &[Channel]) -> Self {
+        let mut sources = [Source::Max; MAX_CHANNELS];
+        for (source, ch) in sources.iter_mut().zip(to) {
+            *source = match from.iter().position(|c| c == ch) {
+                Some(idx) => Source::Channel(idx),
+                None => {
+                    debug_assert_eq!(*ch, Channel::A, "only alpha can be missing from the source");
+                    Source::Max
+                },
+            };
+        }
+        return Self { input: from.len(), output: to.len(), sources };
+    }
+
+    /// Maps the channels of `from` to a swizzle pattern, see [`swizzle`].
+    fn from_pattern(from: &[Channel], pattern: &str) -> Option<Self> {
+        let mut sources = [Source::Max; MAX_CHANNELS];
+        let mut output = 0;
+        for letter in pattern.chars() {
+            if output == MAX_CHANNELS {
+                return None;
+            }
+            sources[output] = match letter {
+                '0' => Source::Zero,
+                '1' => Source::Max,
+                _ => Source::Channel(from.iter().position(|c| c.letter() == letter)?),
+            };
+            output += 1;
+        }
+        if output == 0 {
+            return None;
+        }
+        return Some(Self { input: from.len(), output, sources });
+    }
+
+    /// Reorders the channels of every pixel in `data`, appending the result to `out`.
+    pub(super) fn apply<T: ChannelType>(&self, data: &[T], out: &mut Vec<T>) {
+        let start = out.len();
+        out.resize(start + data.len() / self.input * self.output, T::MAX);
+        self.apply_into(data, &mut out[start..]);
+    }
+
+    /// Reorders the channels of every pixel in `data` into `out`, which must hold exactly as many pixels.
+    pub(super) fn apply_into<T: ChannelType>(&self, data: &[T], out: &mut [T]) {
+        use Source::{Channel as C, Max};
+
+        match (self.input, self.output, &self.sources[..self.output]) {
+            (4, 4, [C(2), C(1), C(0), C(3)]) => shuffle::<T, 4, 4>(data, out, |p| [p[2], p[1], p[0], p[3]]),
+            (4, 3, [C(0), C(1), C(2)]) => shuffle::<T, 4, 3>(data, out, |p| [p[0], p[1], p[2]]),
+            (4, 3, [C(2), C(1), C(0)]) => shuffle::<T, 4, 3>(data, out, |p| [p[2], p[1], p[0]]),
+            (3, 4, [C(0), C(1), C(2), Max]) => shuffle::<T, 3, 4>(data, out, |p| [p[0], p[1], p[2], T::MAX]),
+            (3, 4, [C(2), C(1), C(0), Max]) => shuffle::<T, 3, 4>(data, out, |p| [p[2], p[1], p[0], T::MAX]),
+            (3, 3, [C(2), C(1), C(0)]) => shuffle::<T, 3, 3>(data, out, |p| [p[2], p[1], p[0]]),
+            (3, 1, _) => self.apply_fixed::<T, 3, 1>(data, out),
+            (4, 1, _) => self.apply_fixed::<T, 4, 1>(data, out),
+            (3, 3, _) => self.apply_fixed::<T, 3, 3>(data, out),
+            (3, 4, _) => self.apply_fixed::<T, 3, 4>(data, out),
+            (4, 3, _) => self.apply_fixed::<T, 4, 3>(data, out),
+            (4, 4, _) => self.apply_fixed::<T, 4, 4>(data, out),
+            (4, 5, _) => self.apply_fixed::<T, 4, 5>(data, out),
+            (5, 4, _) => self.apply_fixed::<T, 5, 4>(data, out),
+            (5, 5, _) => self.apply_fixed::<T, 5, 5>(data, out),
+            _ => {
+                let sources = &self.sources[..self.output];
+                for (pixel, target) in data.chunks_exact(self.input).zip(out.chunks_exact_mut(self.output)) {
+                    for (value, source) in target.iter_mut().zip(sources) {
+                        *value = source.get(pixel);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Reorders the channels of every pixel in `data` in place. The map must not drop or add channels.
+    pub(super) fn apply_in_place<T: ChannelType>(&self, data: &mut [T]) {
+        use Source::Channel as C;
+        debug_assert_eq!(self.input, self.output);
+
+        match (self.input, &self.sources[..self.output]) {
+            (4, [C(2), C(1), C(0), C(3)]) => data.as_chunks_mut::<4>().0.iter_mut().for_each(|p| p.swap(0, 2)),
+            (3, [C(2), C(1), C(0)]) => data.as_chunks_mut::<3>().0.iter_mut().for_each(|p| p.swap(0, 2)),
+            (3, _) => self.apply_in_place_fixed::<T, 3>(data),
+            (4, _) => self.apply_in_place_fixed::<T, 4>(data),
+            (5, _) => self.apply_in_place_fixed::<T, 5>(data),
+            _ => {
+                let sources = &self.sources[..self.output];
+                let mut pixel = [T::MAX; MAX_CHANNELS];
+                for target in data.chunks_exact_mut(self.input) {
+                    pixel[..self.input].copy_from_slice(target);
+                    for (value, source) in target.iter_mut().zip(sources) {
+                        *value = source.get(&pixel);
+                    }
+                }
+            },
+        }
+    }
+
+    fn apply_in_place_fixed<T: ChannelType, const N: usize>(&self, data: &mut [T]) {
+        let sources: [Source; N] = std::array::from_fn(|o| self.sources[o]);
+        for pixel in data.as_chunks_mut::<N>().0 {
+            *pixel = sources.map(|source| source.get(pixel));
+        }
+    }
+
+    /// Any reordering of fixed-size pixels, with the sources looked up from the table.
+    fn apply_fixed<T: ChannelType, const I: usize, const O: usize>(&self, data: &[T], out: &mut [T]) {
+        let sources: [Source; O] = std::array::from_fn(|o| self.sources[o]);
+        shuffle::<T, I, O>(data, out, |p| sources.map(|source| source.get(p)));
+    }
+}
+
+impl Source {
+    #[inline(always)]
+    fn get<T: ChannelType>(self, pixel: &[T]) -> T {
+        return match self {
+            Source::Channel(idx) => pixel[idx],
+            Source::Max => T::MAX,
+            Source::Zero => T::from_normalized(0.0),
+        };
+    }
+}
+
+/// Maps every pixel of `I` channels in `data` to a pixel of `O` channels in `out`.
+#[inline(always)]
+fn shuffle<T: ChannelType, const I: usize, const O: usize>(data: &[T], out: &mut [T], f: impl Fn(&[T; I]) -> [T; O]) {
+    let (pixels, _) = data.as_chunks::<I>();
+    let (targets, _) = out.as_chunks_mut::<O>();
+    for (pixel, target) in pixels.iter().zip(targets) {
+        *target = f(pixel);
+    }
+}
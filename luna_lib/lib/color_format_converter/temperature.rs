@@ -0,0 +1,222 @@
+//! Color temperature: the color of a black body radiator at a temperature in Kelvin, and white balance.
+//!
+//! Temperatures follow the Planckian locus, using the approximation of Kim et al., which is valid from `1667K` to `25000K`.
+//! Note that standard daylight illuminants such as `D65` lie slightly above the locus, so `6504K` isn't exactly `sRGB` white.
+
+use super::models::{decode_color, map_colors};
+use super::spaces::{adapt_xyz, linear_srgb_to_xyz, linear_to_srgb, srgb_to_linear, xyz_to_linear_srgb, Triplet};
+use super::{ChannelType, Color, ColorFormat, ColorFormatConverterError};
+
+/// The lowest temperature supported, in Kelvin.
+pub const MIN_KELVIN: f32 = 1667.0;
+/// The highest temperature supported, in Kelvin.
+pub const MAX_KELVIN: f32 = 25000.0;
+
+/// The `Duv` distance from the Planckian locus of a tint of `100.0`.
+const TINT_SCALE: f32 = 0.0002;
+
+/// Calculate the `CIE 1931 xy` chromaticity of a black body at the given temperature.
+///
+/// ## Parameters
+/// - `kelvin`: the temperature, from [`MIN_KELVIN`] to [`MAX_KELVIN`]
+///
+/// ## Returns
+/// A `Result` containing either the `(x, y)` chromaticity, or `OutOfRange` (or `NotANumber`) for an unsupported temperature.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::kelvin_to_xy;
+///
+/// let (x, y) = kelvin_to_xy(2856.0).unwrap(); // illuminant A
+/// assert!((x - 0.4476).abs() < 0.001 && (y - 0.4074).abs() < 0.001);
+/// ```
+pub fn kelvin_to_xy(kelvin: f32) -> Result<(f32, f32), ColorFormatConverterError> {
+    check_kelvin(kelvin)?;
+    let t = kelvin as f64;
+
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / (t * t * t) - 0.2343589e6 / (t * t) + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / (t * t * t) + 2.1070379e6 / (t * t) + 0.2226347e3 / t + 0.240390
+    };
+    let y = if t <= 2222.0 {
+        -1.1063814 * x * x * x - 1.34811020 * x * x + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x * x * x - 1.37418593 * x * x + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x * x * x - 5.87338670 * x * x + 3.75112997 * x - 0.37001483
+    };
+    return Ok((x as f32, y as f32));
+}
+
+/// Estimate the correlated color temperature (`CCT`) of a `CIE 1931 xy` chromaticity, with McCamy's approximation.
+///
+/// The estimate is accurate to a few Kelvin near the Planckian locus from about `2000K` to `12500K`.
+///
+/// ## Returns
+/// A `Result` containing either the temperature in Kelvin, or `OutOfRange` (or `NotANumber`) for chromaticities
+/// that are too far from the locus to estimate.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::xy_to_kelvin;
+///
+/// let cct = xy_to_kelvin(0.3127, 0.3290).unwrap(); // D65
+/// assert!((cct - 6504.0).abs() < 5.0);
+/// ```
+pub fn xy_to_kelvin(x: f32, y: f32) -> Result<f32, ColorFormatConverterError> {
+    if x.is_nan() || y.is_nan() {
+        return Err(ColorFormatConverterError::NotANumber);
+    }
+    let n = (x - 0.3320) / (0.1858 - y);
+    let cct = 449.0 * n * n * n + 3525.0 * n * n + 6823.3 * n + 5520.33;
+    if !cct.is_finite() || !(MIN_KELVIN / 2.0..=MAX_KELVIN * 2.0).contains(&cct) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    return Ok(cct);
+}
+
+/// Calculate the color of a black body at the given temperature, at full brightness.
+///
+/// The color is scaled so its brightest channel is `1.0`, and may be slightly outside of the `sRGB` gamut for very low temperatures.
+///
+/// ## Returns
+/// A `Result` containing either the color, or `OutOfRange` (or `NotANumber`) for an unsupported temperature.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::kelvin_to_rgb;
+///
+/// assert_eq!(kelvin_to_rgb(2700.0).unwrap().to_hex(), "#ffad59"); // warm incandescent
+/// assert_eq!(kelvin_to_rgb(10000.0).unwrap().to_hex(), "#cdd9ff"); // blue sky
+/// ```
+pub fn kelvin_to_rgb(kelvin: f32) -> Result<Color, ColorFormatConverterError> {
+    let (x, y) = kelvin_to_xy(kelvin)?;
+    let linear = xyz_to_linear_srgb(xy_to_xyz(x, y));
+    let max = linear[0].max(linear[1]).max(linear[2]);
+    let [r, g, b] = linear_to_srgb(linear.map(|c| c / max));
+    return Ok(Color::new(r, g, b, 1.0));
+}
+
+/// Estimate the correlated color temperature (`CCT`) of a color, e.g. of a white or gray patch in a photo.
+///
+/// ## Parameters
+/// - `pixel`: a single pixel of `format`
+/// - `format`: color model of `pixel`
+///
+/// ## Returns
+/// A `Result` containing either the temperature in Kelvin, or a [`ColorFormatConverterError`]: `OutOfRange` for black,
+/// or colors too far from the Planckian locus, or any error of decoding the pixel.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{estimate_cct, kelvin_to_rgb, ColorFormat};
+///
+/// let warm = kelvin_to_rgb(3000.0).unwrap().to_rgb8();
+/// let cct = estimate_cct(&warm, ColorFormat::RGB).unwrap();
+/// assert!((cct - 3000.0).abs() < 30.0);
+/// ```
+pub fn estimate_cct<T: ChannelType>(pixel: &[T], format: ColorFormat) -> Result<f32, ColorFormatConverterError> {
+    let [r, g, b, _] = decode_color(pixel, format)?;
+    let [x, y, z] = linear_srgb_to_xyz(srgb_to_linear([r, g, b]));
+    let sum = x + y + z;
+    if sum <= 0.0 {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    return xy_to_kelvin(x / sum, y / sum);
+}
+
+/// Adjust the white balance of a buffer, so that the white of the source light becomes the white of the target light.
+///
+/// For example, a photo taken under `3200K` tungsten light looks orange; balancing it from `3200K` to `6500K` makes it neutral.
+/// The adaptation uses the Bradford transform, in linear `sRGB`.
+///
+/// ## Parameters
+/// - `data`: input slice of pixels, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`
+/// - `source_kelvin`: the temperature of the light the image was captured under
+/// - `target_kelvin`: the temperature of the light it should look like it was captured under
+/// - `tint`: from `-100.0` to `100.0`, the green-magenta correction, where positive values add magenta and negative values add green.
+///   `100.0` corresponds to a source light `0.02` `Duv` above (greener than) the Planckian locus.
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec` in the same format, or a [`ColorFormatConverterError`]:
+/// `OutOfRange` (or `NotANumber`) for invalid temperatures or tint, or any of the errors of [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic).
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{white_balance, ColorFormat};
+///
+/// // A white wall photographed under tungsten light
+/// let orange_wall = vec![255u8, 180, 110, 255];
+/// let balanced = white_balance(&orange_wall, ColorFormat::RGBA, 2900.0, 6500.0, 0.0).unwrap();
+/// assert!((balanced[0] as i32 - balanced[2] as i32).abs() < 40);
+///
+/// // Same temperature and no tint changes nothing
+/// assert_eq!(white_balance(&orange_wall, ColorFormat::RGBA, 5000.0, 5000.0, 0.0), Ok(orange_wall));
+/// ```
+pub fn white_balance<T: ChannelType>(
+    data: &[T],
+    format: ColorFormat,
+    source_kelvin: f32,
+    target_kelvin: f32,
+    tint: f32
+) -> Result<Vec<T>, ColorFormatConverterError> {
+
+    if tint.is_nan() {
+        return Err(ColorFormatConverterError::NotANumber);
+    }
+    if !(-100.0..=100.0).contains(&tint) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    let source = white_point(source_kelvin, tint * TINT_SCALE)?;
+    let target = white_point(target_kelvin, 0.0)?;
+
+    return map_colors(data, format, |[r, g, b, a]| {
+        let xyz = adapt_xyz(linear_srgb_to_xyz(srgb_to_linear([r, g, b])), source, target);
+        let [r, g, b] = linear_to_srgb(xyz_to_linear_srgb(xyz));
+        [r, g, b, a]
+    });
+}
+
+fn check_kelvin(kelvin: f32) -> Result<(), ColorFormatConverterError> {
+    if kelvin.is_nan() {
+        return Err(ColorFormatConverterError::NotANumber);
+    }
+    if !(MIN_KELVIN..=MAX_KELVIN).contains(&kelvin) {
+        return Err(ColorFormatConverterError::OutOfRange);
+    }
+    return Ok(());
+}
+
+/// `xy` chromaticity to `XYZ` with `Y = 1.0`.
+fn xy_to_xyz(x: f32, y: f32) -> Triplet {
+    return [x / y, 1.0, (1.0 - x - y) / y];
+}
+
+/// The `XYZ` white of a black body at the given temperature, moved `duv` away from the Planckian locus
+/// in the `CIE 1960 uv` diagram, where positive values are above (greener than) the locus.
+fn white_point(kelvin: f32, duv: f32) -> Result<Triplet, ColorFormatConverterError> {
+    let (x, y) = kelvin_to_xy(kelvin)?;
+    if duv == 0.0 {
+        return Ok(xy_to_xyz(x, y));
+    }
+
+    let to_uv = |(x, y): (f32, f32)| {
+        let d = -2.0 * x + 12.0 * y + 3.0;
+        (4.0 * x / d, 6.0 * y / d)
+    };
+    let (u, v) = to_uv((x, y));
+
+    // the normal of the locus, from its tangent towards higher temperatures
+    let (lower, upper) = ((kelvin - 10.0).max(MIN_KELVIN), (kelvin + 10.0).min(MAX_KELVIN));
+    let (u0, v0) = to_uv(kelvin_to_xy(lower)?);
+    let (u1, v1) = to_uv(kelvin_to_xy(upper)?);
+    let (du, dv) = (u1 - u0, v1 - v0);
+    let length = (du * du + dv * dv).sqrt();
+    let (nu, nv) = if du > 0.0 { (-dv / length, du / length) } else { (dv / length, -du / length) };
+
+    let (u, v) = (u + nu * duv, v + nv * duv);
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    return Ok(xy_to_xyz(3.0 * u / d, 2.0 * v / d));
+}
@@ -0,0 +1,220 @@
+//! Terminal colors: mapping colors to the xterm 16 and 256 color palettes, and `SGR` escape sequences
+//! for printing colored text and image previews.
+
+use std::sync::OnceLock;
+
+use super::{convert_vec_color_model, Color, ColorFormat, ColorFormatConverterError};
+
+/// The `SGR` escape sequence that resets all colors and attributes to the terminal's defaults.
+pub const SGR_RESET: &str = "\x1b[0m";
+
+/// The default colors of the 16 basic xterm colors. Most terminals let themes change these.
+const ANSI16: [[u8; 3]; 16] = [
+    [0, 0, 0], [205, 0, 0], [0, 205, 0], [205, 205, 0],
+    [0, 0, 238], [205, 0, 205], [0, 205, 205], [229, 229, 229],
+    [127, 127, 127], [255, 0, 0], [0, 255, 0], [255, 255, 0],
+    [92, 92, 255], [255, 0, 255], [0, 255, 255], [255, 255, 255],
+];
+
+/// The channel levels of the 6x6x6 color cube of the 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors a terminal supports, and so how colors are written in escape sequences.
+///
+/// - `Ansi16`: The 16 basic colors, supported by virtually every terminal.
+/// - `Ansi256`: The xterm 256 color palette, of the 16 basic colors, a 6x6x6 color cube and 24 grays.
+/// - `TrueColor`: Any 24-bit `RGB` color, supported by most modern terminals (check `COLORTERM=truecolor`).
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TerminalColorMode {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// The color of an xterm palette index, using the xterm defaults for the 16 basic colors.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::xterm_color;
+///
+/// assert_eq!(xterm_color(9).to_hex(), "#ff0000");
+/// assert_eq!(xterm_color(208).to_hex(), "#ff8700"); // from the color cube
+/// assert_eq!(xterm_color(244).to_hex(), "#808080"); // from the grays
+/// ```
+pub fn xterm_color(index: u8) -> Color {
+    let [r, g, b] = xterm_rgb(index);
+    return Color::from_rgb8(r, g, b);
+}
+
+/// Find the nearest of the 16 basic terminal colors, by perceptual distance in `OKLab`.
+///
+/// ## Returns
+/// The palette index, from `0` to `15`. Alpha is ignored.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{nearest_ansi16, Color};
+///
+/// assert_eq!(nearest_ansi16(Color::from_rgb8(200, 30, 20)), 1); // red
+/// assert_eq!(nearest_ansi16(Color::from_rgb8(250, 250, 240)), 15); // bright white
+/// ```
+pub fn nearest_ansi16(color: Color) -> u8 {
+    return nearest(&palette_oklab()[..16], color);
+}
+
+/// Find the nearest color of the xterm 256 color palette, by perceptual distance in `OKLab`.
+///
+/// Only the color cube and the grays (`16` to `255`) are searched, since the 16 basic colors are often changed by terminal themes.
+///
+/// ## Returns
+/// The palette index, from `16` to `255`. Alpha is ignored.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{nearest_ansi256, Color};
+///
+/// assert_eq!(nearest_ansi256(Color::from_rgb8(255, 135, 0)), 208);
+/// assert_eq!(nearest_ansi256(Color::from_rgb8(128, 128, 128)), 244);
+/// ```
+pub fn nearest_ansi256(color: Color) -> u8 {
+    return 16 + nearest(&palette_oklab()[16..], color);
+}
+
+/// Create the `SGR` escape sequence that sets the foreground (text) color of a terminal.
+///
+/// ## Parameters
+/// - `color`: the color to set, mapped to the nearest palette color for `Ansi16` and `Ansi256`. Alpha is ignored.
+/// - `mode`: the colors the terminal supports
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{sgr_foreground, Color, TerminalColorMode, SGR_RESET};
+///
+/// let orange = Color::from_rgb8(255, 135, 0);
+/// assert_eq!(sgr_foreground(orange, TerminalColorMode::TrueColor), "\x1b[38;2;255;135;0m");
+/// assert_eq!(sgr_foreground(orange, TerminalColorMode::Ansi256), "\x1b[38;5;208m");
+/// assert_eq!(sgr_foreground(orange, TerminalColorMode::Ansi16), "\x1b[91m");
+///
+/// println!("{}orange text{}", sgr_foreground(orange, TerminalColorMode::TrueColor), SGR_RESET);
+/// ```
+pub fn sgr_foreground(color: Color, mode: TerminalColorMode) -> String {
+    return sgr(color, mode, false);
+}
+
+/// Create the `SGR` escape sequence that sets the background color of a terminal. See [`sgr_foreground`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{sgr_background, Color, TerminalColorMode};
+///
+/// let navy = Color::from_rgb8(0, 0, 128);
+/// assert_eq!(sgr_background(navy, TerminalColorMode::TrueColor), "\x1b[48;2;0;0;128m");
+/// assert_eq!(sgr_background(navy, TerminalColorMode::Ansi16), "\x1b[44m");
+/// ```
+pub fn sgr_background(color: Color, mode: TerminalColorMode) -> String {
+    return sgr(color, mode, true);
+}
+
+/// Render a buffer of 8-bit pixels as text for a terminal, e.g. for previewing images in a CLI.
+///
+/// Every character is an upper half block (`▀`) whose foreground is one pixel and whose background is the pixel below it,
+/// so every line of text shows two rows of pixels. An odd last row keeps the terminal's background below it.
+/// Every line ends with [`SGR_RESET`] and a newline. Alpha is ignored.
+///
+/// ## Parameters
+/// - `data`: input byte-slice, length must be a multiple of `format.bytes_per_pixel()`
+/// - `format`: color model of `data`
+/// - `width`: the width of the buffer in pixels, which must divide the amount of pixels
+/// - `mode`: the colors the terminal supports
+///
+/// ## Returns
+/// A `Result` containing either the text, or a [`ColorFormatConverterError`]: `InvalidDimensions` if `width` doesn't match
+/// the buffer, or any of the errors of [`convert_vec_color_model`].
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{render_terminal, ColorFormat, TerminalColorMode};
+///
+/// // A 1x2 image of a red pixel above a blue one
+/// let rgb = vec![255, 0, 0,  0, 0, 255];
+/// let text = render_terminal(&rgb, ColorFormat::RGB, 1, TerminalColorMode::TrueColor).unwrap();
+/// assert_eq!(text, "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[0m\n");
+/// ```
+pub fn render_terminal(data: &[u8], format: ColorFormat, width: usize, mode: TerminalColorMode) -> Result<String, ColorFormatConverterError> {
+    let rgb = if format == ColorFormat::RGB { data.to_vec() } else { convert_vec_color_model(data, format, ColorFormat::RGB)? };
+    if rgb.is_empty() || !rgb.len().is_multiple_of(3) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    let pixels = rgb.len() / 3;
+    if width == 0 || !pixels.is_multiple_of(width) {
+        return Err(ColorFormatConverterError::InvalidDimensions);
+    }
+    let color = |row: &[u8], x: usize| Color::from_rgb8(row[x * 3], row[x * 3 + 1], row[x * 3 + 2]);
+
+    let mut text = String::new();
+    for rows in rgb.chunks(width * 6) {
+        let (top, bottom) = rows.split_at(rows.len().min(width * 3));
+        for x in 0..width {
+            text.push_str(&sgr_foreground(color(top, x), mode));
+            if !bottom.is_empty() {
+                text.push_str(&sgr_background(color(bottom, x), mode));
+            }
+            text.push('▀');
+        }
+        text.push_str(SGR_RESET);
+        text.push('\n');
+    }
+    return Ok(text);
+}
+
+fn xterm_rgb(index: u8) -> [u8; 3] {
+    return match index {
+        0..=15 => ANSI16[index as usize],
+        16..=231 => {
+            let i = (index - 16) as usize;
+            [CUBE_LEVELS[i / 36], CUBE_LEVELS[i / 6 % 6], CUBE_LEVELS[i % 6]]
+        },
+        _ => [8 + (index - 232) * 10; 3],
+    };
+}
+
+/// The `OKLab` values of the xterm palette, computed once.
+fn palette_oklab() -> &'static [[f32; 3]; 256] {
+    static PALETTE: OnceLock<[[f32; 3]; 256]> = OnceLock::new();
+    return PALETTE.get_or_init(|| std::array::from_fn(|i| xterm_color(i as u8).to_oklab()));
+}
+
+/// The index of the palette color nearest to the color in `OKLab`.
+fn nearest(palette: &[[f32; 3]], color: Color) -> u8 {
+    let lab = color.to_oklab();
+    let mut best = (0, f32::INFINITY);
+    for (i, entry) in palette.iter().enumerate() {
+        let d = (entry[0] - lab[0]).powi(2) + (entry[1] - lab[1]).powi(2) + (entry[2] - lab[2]).powi(2);
+        if d < best.1 {
+            best = (i, d);
+        }
+    }
+    return best.0 as u8;
+}
+
+fn sgr(color: Color, mode: TerminalColorMode, background: bool) -> String {
+    let layer = if background { 48 } else { 38 };
+    let code = match mode {
+        TerminalColorMode::Ansi16 => {
+            let index = nearest_ansi16(color);
+            let base = match (background, index < 8) {
+                (false, true) => 30,
+                (false, false) => 90 - 8,
+                (true, true) => 40,
+                (true, false) => 100 - 8,
+            };
+            format!("{}", base + index)
+        },
+        TerminalColorMode::Ansi256 => format!("{};5;{}", layer, nearest_ansi256(color)),
+        TerminalColorMode::TrueColor => {
+            let [r, g, b] = color.to_rgb8();
+            format!("{};2;{};{};{}", layer, r, g, b)
+        },
+    };
+    return format!("\x1b[{}m", code);
+}
@@ -0,0 +1,206 @@
+//! Transfer functions ("gamma"), for going between gamma-encoded values and linear light.
+
+use std::sync::OnceLock;
+
+use super::models::{family, Family};
+use super::{channel_order, Channel, ColorFormat, ColorFormatConverterError};
+
+/// A transfer function, describing how linear light is encoded into channel values.
+///
+/// Most 8-bit images are `sRGB` encoded, meaning a value of `128` is not half as bright as `255`, but about a fifth.
+/// Math on brightness, such as blending, averaging or scaling, should be done on linear values instead,
+/// by decoding the channels first and encoding them again afterwards.
+///
+/// - `SRGB`: The `sRGB` transfer function (IEC 61966-2-1), used by almost all images and displays.
+/// - `Rec709`: The `BT.709` camera transfer function, used by HD video.
+/// - `Gamma22`: A pure `2.2` power function, a common approximation of `sRGB`.
+///
+/// Values outside of `0.0-1.0` are mirrored around `0.0`, so negative values (from out of gamut colors) keep their sign.
+///
+/// # Examples
+/// ```rust
+/// # use luna::color_format_converter::TransferFunction;
+///
+/// let linear = TransferFunction::SRGB.decode(128.0 / 255.0);
+/// assert!((linear - 0.2158).abs() < 0.0001);
+/// assert!((TransferFunction::SRGB.encode(linear) - 128.0 / 255.0).abs() < 0.0001);
+///
+/// assert_eq!(TransferFunction::Gamma22.decode(1.0), 1.0);
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TransferFunction {
+    SRGB,
+    Rec709,
+    Gamma22,
+}
+
+impl TransferFunction {
+    /// Decodes an encoded value in `0.0-1.0` into linear light.
+    pub fn decode(&self, value: f32) -> f32 {
+        let v = value.abs();
+        let linear = match self {
+            TransferFunction::SRGB => if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) },
+            TransferFunction::Rec709 => if v < 0.081 { v / 4.5 } else { ((v + 0.099) / 1.099).powf(1.0 / 0.45) },
+            TransferFunction::Gamma22 => v.powf(2.2),
+        };
+        return linear.copysign(value);
+    }
+
+    /// Encodes linear light in `0.0-1.0` into an encoded value.
+    pub fn encode(&self, value: f32) -> f32 {
+        let l = value.abs();
+        let encoded = match self {
+            TransferFunction::SRGB => if l <= 0.0031308 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 },
+            TransferFunction::Rec709 => if l < 0.018 { l * 4.5 } else { 1.099 * l.powf(0.45) - 0.099 },
+            TransferFunction::Gamma22 => l.powf(1.0 / 2.2),
+        };
+        return encoded.copysign(value);
+    }
+
+    /// Lookup table from every 8-bit encoded value to linear light.
+    fn decode_lut(&self) -> &'static [f32; 256] {
+        static SRGB: OnceLock<[f32; 256]> = OnceLock::new();
+        static REC709: OnceLock<[f32; 256]> = OnceLock::new();
+        static GAMMA22: OnceLock<[f32; 256]> = OnceLock::new();
+
+        let lock = match self {
+            TransferFunction::SRGB => &SRGB,
+            TransferFunction::Rec709 => &REC709,
+            TransferFunction::Gamma22 => &GAMMA22,
+        };
+        return lock.get_or_init(|| std::array::from_fn(|i| self.decode(i as f32 / 255.0)));
+    }
+
+    /// Lookup table from linear light, quantized to `ENCODE_LUT_SIZE` steps, to 8-bit encoded values.
+    fn encode_lut(&self) -> &'static [u8; ENCODE_LUT_SIZE] {
+        static SRGB: OnceLock<[u8; ENCODE_LUT_SIZE]> = OnceLock::new();
+        static REC709: OnceLock<[u8; ENCODE_LUT_SIZE]> = OnceLock::new();
+        static GAMMA22: OnceLock<[u8; ENCODE_LUT_SIZE]> = OnceLock::new();
+
+        let lock = match self {
+            TransferFunction::SRGB => &SRGB,
+            TransferFunction::Rec709 => &REC709,
+            TransferFunction::Gamma22 => &GAMMA22,
+        };
+        return lock.get_or_init(|| std::array::from_fn(|i| {
+            let linear = i as f32 / (ENCODE_LUT_SIZE - 1) as f32;
+            (self.encode(linear) * 255.0).round() as u8
+        }));
+    }
+
+    /// Decodes an 8-bit encoded value into linear light, using a lookup table.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::TransferFunction;
+    ///
+    /// assert_eq!(TransferFunction::SRGB.decode_u8(255), 1.0);
+    /// assert_eq!(TransferFunction::SRGB.decode_u8(128), TransferFunction::SRGB.decode(128.0 / 255.0));
+    /// ```
+    #[inline]
+    pub fn decode_u8(&self, value: u8) -> f32 {
+        return self.decode_lut()[value as usize];
+    }
+
+    /// Encodes linear light in `0.0-1.0` into an 8-bit value, using a lookup table.
+    /// Inputs outside of `0.0-1.0` are clamped.
+    ///
+    /// The table has enough steps that the result is the same as rounding [`TransferFunction::encode`],
+    /// except for the very darkest values, where it may be off by one.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use luna::color_format_converter::TransferFunction;
+    ///
+    /// assert_eq!(TransferFunction::SRGB.encode_u8(1.0), 255);
+    /// assert_eq!(TransferFunction::SRGB.encode_u8(0.2158), 128);
+    /// ```
+    #[inline]
+    pub fn encode_u8(&self, value: f32) -> u8 {
+        let index = (value.clamp(0.0, 1.0) * (ENCODE_LUT_SIZE - 1) as f32).round() as usize;
+        return self.encode_lut()[index];
+    }
+}
+
+const ENCODE_LUT_SIZE: usize = 4096;
+
+/// Decode 8-bit encoded pixels into linear light, using a lookup table.
+///
+/// Every color channel is decoded with the given transfer function, while alpha is only normalized to `0.0-1.0`.
+/// The output keeps the channel order of `format`, and can be converted to any other format as
+/// `LinearRGB(A)` (after reordering to `RGB(A)`) with [`convert_vec_color_model_generic`](super::convert_vec_color_model_generic).
+///
+/// ## Parameters
+/// - `data`: input byte-slice, length must be a multiple of `format.channel_count()`
+/// - `format`: one of the `RGB` and `RGBA` formats or their permutations, or `Gray`/`GrayA`
+/// - `transfer`: the transfer function the input is encoded with
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<f32>` of linear values in `0.0-1.0`, or a [`ColorFormatConverterError`]:
+/// `UnsupportedFormat` for any other format, or `InvalidInputLength`.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{decode_transfer_u8, encode_transfer_u8, ColorFormat, TransferFunction};
+///
+/// // Averaging black and white in linear light gives 188, not 128
+/// let rgba = vec![0, 0, 0, 255,  255, 255, 255, 255];
+/// let linear = decode_transfer_u8(&rgba, ColorFormat::RGBA, TransferFunction::SRGB).unwrap();
+/// let average: Vec<f32> = (0..4).map(|i| (linear[i] + linear[i + 4]) / 2.0).collect();
+/// let encoded = encode_transfer_u8(&average, ColorFormat::RGBA, TransferFunction::SRGB);
+/// assert_eq!(encoded, Ok(vec![188, 188, 188, 255]));
+/// ```
+pub fn decode_transfer_u8(data: &[u8], format: ColorFormat, transfer: TransferFunction) -> Result<Vec<f32>, ColorFormatConverterError> {
+    let order = color_channels(format, data.len())?;
+    let lut = transfer.decode_lut();
+
+    return Ok(data.iter()
+        .zip(order.iter().cycle())
+        .map(|(&v, ch)| if *ch == Channel::A { v as f32 / 255.0 } else { lut[v as usize] })
+        .collect());
+}
+
+/// Encode linear light into 8-bit encoded pixels, using a lookup table.
+///
+/// This is the reverse of [`decode_transfer_u8`]. Every color channel is encoded with the given transfer function,
+/// while alpha is only scaled to `0-255`. Values outside of `0.0-1.0` are clamped.
+///
+/// ## Parameters
+/// - `data`: linear input values, length must be a multiple of `format.channel_count()`
+/// - `format`: one of the `RGB` and `RGBA` formats or their permutations, or `Gray`/`GrayA`
+/// - `transfer`: the transfer function to encode with
+///
+/// ## Returns
+/// A `Result` containing either a new `Vec<u8>`, or a [`ColorFormatConverterError`]:
+/// `UnsupportedFormat` for any other format, `InvalidInputLength`, or `NotANumber` for `NaN` inputs.
+///
+/// ## Examples
+/// ```rust
+/// # use luna::color_format_converter::{encode_transfer_u8, ColorFormat, TransferFunction};
+///
+/// let linear = vec![0.0, 0.2158, 1.0];
+/// assert_eq!(encode_transfer_u8(&linear, ColorFormat::RGB, TransferFunction::SRGB), Ok(vec![0, 128, 255]));
+/// ```
+pub fn encode_transfer_u8(data: &[f32], format: ColorFormat, transfer: TransferFunction) -> Result<Vec<u8>, ColorFormatConverterError> {
+    let order = color_channels(format, data.len())?;
+    if data.iter().any(|v| v.is_nan()) {
+        return Err(ColorFormatConverterError::NotANumber);
+    }
+
+    return Ok(data.iter()
+        .zip(order.iter().cycle())
+        .map(|(&v, ch)| if *ch == Channel::A { (v.clamp(0.0, 1.0) * 255.0).round() as u8 } else { transfer.encode_u8(v) })
+        .collect());
+}
+
+/// The channel order of a format the transfer functions can be applied to, after checking the input length.
+fn color_channels(format: ColorFormat, len: usize) -> Result<&'static [Channel], ColorFormatConverterError> {
+    let order = channel_order(format);
+    if !matches!(family(format), Family::Rgb | Family::Gray) {
+        return Err(ColorFormatConverterError::UnsupportedFormat);
+    }
+    if len == 0 || !len.is_multiple_of(order.len()) {
+        return Err(ColorFormatConverterError::InvalidInputLength);
+    }
+    return Ok(order);
+}
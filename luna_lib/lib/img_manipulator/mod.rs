@@ -5,7 +5,7 @@
 use image::{self, DynamicImage, ImageError, imageops, ImageFormat};
 use bytesize::ByteSize;
 
-pub const VERSION: crate::Version = crate::Version::new(0, 4, 1);
+pub const VERSION: crate::Version = crate::Version::new(0, 9, 0);
 
 /// An enum which can be returned when attempting to open an image from a path and decode it.
 /// It can either be a success with the decoded image and its format, or a failure with an [ImageError].
@@ -260,3 +260,106 @@ pub fn flip_vertical(img: &mut DynamicImage) {
 pub fn flip_horizontal(img: &mut DynamicImage) {
     *img = DynamicImage::fliph(img);
 }
+
+/// Simulates how the image looks to a viewer with a color vision deficiency, see
+/// [`simulate_color_vision_deficiency`](crate::color_format_converter::simulate_color_vision_deficiency).
+/// The image is converted to 8-bit `RGBA`.
+#[cfg(feature = "color_format_converter")]
+pub fn simulate_color_vision_deficiency(
+    img: &mut DynamicImage,
+    deficiency: crate::color_format_converter::ColorVisionDeficiency,
+    severity: f32
+) -> Result<(), crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{self, ColorFormat};
+
+    let (width, height) = (img.width(), img.height());
+    let rgba = color_format_converter::simulate_color_vision_deficiency(&into_rgba8(img), ColorFormat::RGBA, deficiency, severity)?;
+    *img = DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, rgba).expect("Buffer size is unchanged by the simulation."));
+    return Ok(());
+}
+
+/// Adjusts the white balance of the image from the light it was captured under to another, see
+/// [`white_balance`](crate::color_format_converter::white_balance).
+/// The image is converted to 8-bit `RGBA`.
+#[cfg(feature = "color_format_converter")]
+pub fn white_balance(
+    img: &mut DynamicImage,
+    source_kelvin: f32,
+    target_kelvin: f32,
+    tint: f32
+) -> Result<(), crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{self, ColorFormat};
+
+    let (width, height) = (img.width(), img.height());
+    let rgba = color_format_converter::white_balance(&into_rgba8(img), ColorFormat::RGBA, source_kelvin, target_kelvin, tint)?;
+    *img = DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, rgba).expect("Buffer size is unchanged by the adjustment."));
+    return Ok(());
+}
+
+/// Creates an image of a horizontal gradient, e.g. for test charts.
+/// See [`Gradient`](crate::color_format_converter::Gradient) for building the gradient.
+#[cfg(feature = "color_format_converter")]
+pub fn gradient_image(
+    gradient: &crate::color_format_converter::Gradient,
+    width: u32,
+    height: u32
+) -> Result<DynamicImage, crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{ColorFormat, ColorFormatConverterError};
+
+    if height == 0 {
+        return Err(ColorFormatConverterError::InvalidDimensions);
+    }
+    let row = gradient.render::<u8>(width as usize, ColorFormat::RGBA)?;
+    let rgba = row.repeat(height as usize);
+    return Ok(DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, rgba).expect("Buffer size matches the dimensions.")));
+}
+
+/// Reduces the image to at most `max_colors` colors, see [`quantize`](crate::color_format_converter::quantize).
+/// The image is converted to 8-bit `RGBA`.
+#[cfg(feature = "color_format_converter")]
+pub fn reduce_colors(
+    img: &mut DynamicImage,
+    max_colors: usize,
+    method: crate::color_format_converter::QuantizeMethod
+) -> Result<(), crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{self, ColorFormat};
+
+    let (width, height) = (img.width(), img.height());
+    let rgba = color_format_converter::quantize(&into_rgba8(img), ColorFormat::RGBA, max_colors, method)?.to_rgba8();
+    *img = DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, rgba).expect("Buffer size is unchanged by the quantization."));
+    return Ok(());
+}
+
+/// Grades the image with a color lookup table, see [`apply_lut`](crate::color_format_converter::apply_lut).
+/// The image is converted to 8-bit `RGBA`.
+#[cfg(feature = "color_format_converter")]
+pub fn apply_lut(
+    img: &mut DynamicImage,
+    lut: &crate::color_format_converter::CubeLut,
+    interpolation: crate::color_format_converter::LutInterpolation
+) -> Result<(), crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{self, ColorFormat};
+
+    let (width, height) = (img.width(), img.height());
+    let rgba = color_format_converter::apply_lut(&into_rgba8(img), ColorFormat::RGBA, lut, interpolation)?;
+    *img = DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, rgba).expect("Buffer size is unchanged by the LUT."));
+    return Ok(());
+}
+
+/// Finds the most common colors of the image, with the percentage of the image each covers,
+/// see [`dominant_colors`](crate::color_format_converter::dominant_colors).
+#[cfg(feature = "color_format_converter")]
+pub fn dominant_colors(
+    img: &DynamicImage,
+    count: usize
+) -> Result<Vec<(crate::color_format_converter::Color, f32)>, crate::color_format_converter::ColorFormatConverterError> {
+
+    use crate::color_format_converter::{self, ColorFormat};
+
+    return color_format_converter::dominant_colors(&into_rgba8(img), ColorFormat::RGBA, count);
+}